@@ -119,4 +119,9 @@ pub struct DevAttributes {
     pub dev_channel: u64,
     pub dev_message: bool,
     pub flux_workspace_root_path_override: String,
+    pub flux_cgroup_root: Option<String>,
+    pub flux_temp_root: Option<String>,
+    pub flux_max_concurrent_jobs: Option<usize>,
+    pub flux_max_queued_jobs: Option<usize>,
+    pub flux_max_jobs_per_user: Option<usize>,
 }
@@ -3,7 +3,9 @@ use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use assyst_database::DatabaseHandler;
-use prometheus::{register_int_counter, register_int_gauge_vec, IntCounter, IntGaugeVec};
+use prometheus::{
+    register_int_counter, register_int_counter_vec, register_int_gauge_vec, IntCounter, IntCounterVec, IntGaugeVec,
+};
 use tracing::debug;
 
 use crate::util::process::get_processes_mem_usage;
@@ -20,6 +22,7 @@ pub struct MetricsHandler {
     pub commands: IntCounter,
     pub total_commands_rate_tracker: Mutex<RateTracker>,
     pub individual_commands_rate_trackers: tokio::sync::Mutex<HashMap<&'static str /* command name */, RateTracker>>,
+    pub flux_cache_lookups: IntCounterVec,
     pub database_handler: Arc<DatabaseHandler>,
 }
 impl MetricsHandler {
@@ -34,6 +37,11 @@ impl MetricsHandler {
             commands: register_int_counter!("commands", "Total number of commands executed")?,
             total_commands_rate_tracker: Mutex::new(RateTracker::new(Duration::from_secs(60))),
             individual_commands_rate_trackers: tokio::sync::Mutex::new(HashMap::new()),
+            flux_cache_lookups: register_int_counter_vec!(
+                "flux_cache_lookups",
+                "Lookups in the Flux output cache",
+                &["result"]
+            )?,
             database_handler,
         })
     }
@@ -101,6 +109,14 @@ impl MetricsHandler {
         self.total_commands_rate_tracker.lock().unwrap().get_rate()
     }
 
+    pub fn add_flux_cache_hit(&self) {
+        self.flux_cache_lookups.with_label_values(&["hit"]).inc();
+    }
+
+    pub fn add_flux_cache_miss(&self) {
+        self.flux_cache_lookups.with_label_values(&["miss"]).inc();
+    }
+
     pub async fn add_individual_command_usage(&self, command_name: &'static str) {
         let mut lock = self.individual_commands_rate_trackers.lock().await;
         let entry = lock.get_mut(&command_name);
@@ -1,7 +1,7 @@
 use std::cmp::min;
 use std::ops::Range;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Type {
     GIF,
     JPEG,
@@ -9,6 +9,7 @@ use assyst_database::DatabaseHandler;
 use assyst_database::model::active_guild_premium_entitlement::ActiveGuildPremiumEntitlement;
 use assyst_database::model::badtranslator_channel::BadTranslatorChannel;
 use assyst_flux_iface::FluxHandler;
+use assyst_flux_iface::queue::QueueLimits;
 use twilight_http::Client as HttpClient;
 use twilight_http::client::InteractionClient;
 use twilight_model::id::Id;
@@ -16,7 +17,7 @@ use twilight_model::id::marker::ApplicationMarker;
 
 use crate::bad_translator::{BadTranslator, BadTranslatorEntry};
 use crate::command::componentctxt::ComponentCtxts;
-use crate::command_ratelimits::CommandRatelimits;
+use crate::command_ratelimits::{CommandRatelimits, TagExecutionBudgets};
 use crate::persistent_cache_handler::PersistentCacheHandler;
 use crate::replies::Replies;
 use crate::rest::patreon::Patron;
@@ -59,6 +60,8 @@ pub struct Assyst {
     /// All command ratelimits, in the format <(guild/user id, command name) => time command was
     /// ran>
     pub command_ratelimits: CommandRatelimits,
+    /// Per-user and per-tag tag execution time
+    pub tag_execution_budgets: TagExecutionBudgets,
     /// All entitlements. At present, these entitlements are a single tier of guild subscription.
     /// `Arc`ed since it's also included as part of the Flux handler
     pub entitlements: Arc<Mutex<HashMap<i64, ActiveGuildPremiumEntitlement>>>,
@@ -77,6 +80,8 @@ impl Assyst {
             ActiveGuildPremiumEntitlement::get_all(&database_handler).await?,
         ));
 
+        let metrics_handler = Arc::new(MetricsHandler::new(database_handler.clone())?);
+
         Ok(Assyst {
             bad_translator: BadTranslator::new(),
             persistent_cache_handler: PersistentCacheHandler::new(CACHE_PIPE_PATH),
@@ -84,7 +89,7 @@ impl Assyst {
             http_client: http_client.clone(),
             application_id: current_application.id,
             premium_users: premium_users.clone(),
-            metrics_handler: Arc::new(MetricsHandler::new(database_handler.clone())?),
+            metrics_handler: metrics_handler.clone(),
             reqwest_client: reqwest::Client::new(),
             tasks: Mutex::new(vec![]),
             shard_count,
@@ -93,9 +98,12 @@ impl Assyst {
                 database_handler.clone(),
                 Arc::new(Mutex::new(HashMap::new())),
                 entitlements.clone(),
-            ),
+                metrics_handler,
+            )
+            .with_queue_limits(QueueLimits::from_config()),
             rest_cache_handler: RestCacheHandler::new(http_client.clone()),
             command_ratelimits: CommandRatelimits::new(),
+            tag_execution_budgets: TagExecutionBudgets::new(),
             entitlements,
             component_contexts: ComponentCtxts::new(),
         })
@@ -2,6 +2,7 @@ use std::fmt::Display;
 
 use assyst_common::util::discord::{channel_mention_to_id, get_avatar_url, id_from_mention, user_mention_to_id};
 use assyst_common::util::{parse_to_millis, regex};
+use assyst_flux_iface::limits::MAX_INPUTS;
 use assyst_string_fmt::markdown::parse_codeblock;
 use serde::Deserialize;
 use twilight_model::application::command::CommandOption;
@@ -621,27 +622,24 @@ impl ParseArgument for RestNoFlags {
 pub struct ImageUrl(pub String);
 
 impl ImageUrl {
-    async fn from_mention_raw_message(ctxt: &mut RawMessageParseCtxt<'_>, label: Label) -> Result<Self, TagParseError> {
-        let word = ctxt.next_word(label)?;
-
+    /// Gets the avatar of the user mentioned by `word`.
+    async fn from_mention(cx: &CommandCtxt<'_>, word: &str) -> Result<Self, TagParseError> {
         let user_id = id_from_mention(word).ok_or(TagParseError::NoMention)?;
 
         if user_id == 0 {
             return Err(TagParseError::NoMention);
         }
 
-        let user = ctxt
-            .cx
-            .assyst()
-            .http_client
-            .user(Id::new(user_id))
-            .await?
-            .model()
-            .await?;
+        let user = cx.assyst().http_client.user(Id::new(user_id)).await?.model().await?;
 
         Ok(Self(get_avatar_url(&user)))
     }
 
+    async fn from_mention_raw_message(ctxt: &mut RawMessageParseCtxt<'_>, label: Label) -> Result<Self, TagParseError> {
+        let word = ctxt.next_word(label)?;
+        Self::from_mention(&ctxt.cx, word).await
+    }
+
     async fn from_mention_command_option(
         ctxt: &mut InteractionCommandParseCtxt<'_>,
         label: Label,
@@ -649,22 +647,7 @@ impl ImageUrl {
         let word = &ctxt.option_by_name(&label.unwrap().0)?.value;
 
         if let CommandOptionValue::String(option) = word {
-            let user_id = id_from_mention(option).ok_or(TagParseError::NoMention)?;
-
-            if user_id == 0 {
-                return Err(TagParseError::NoMention);
-            }
-
-            let user = ctxt
-                .cx
-                .assyst()
-                .http_client
-                .user(Id::new(user_id))
-                .await?
-                .model()
-                .await?;
-
-            Ok(Self(get_avatar_url(&user)))
+            Self::from_mention(&ctxt.cx, option).await
         } else {
             Err(TagParseError::MismatchedCommandOptionType((
                 "String (mention aregument)".to_owned(),
@@ -912,6 +895,30 @@ impl ImageUrl {
 
         Err(TagParseError::NoImageInHistory)
     }
+
+    /// Resolves URLs of pages that embed an image, such as Tenor and Klipy, to the URL of the image.
+    async fn resolve(self, cx: &CommandCtxt<'_>) -> Result<Self, TagParseError> {
+        let ImageUrl(mut url) = self;
+
+        // tenor urls only typically return a png, so this code visits the url
+        // and extracts the appropriate GIF url from the page.
+        if url.starts_with("https://tenor.com/view") {
+            let page = cx.assyst().reqwest_client.get(&url).send().await?.text().await?;
+
+            let gif_url = regex::TENOR_GIF.find(&page).ok_or(TagParseError::MediaDownloadFail)?;
+            url = gif_url.as_str().to_owned();
+        }
+
+        if url.starts_with("https://klipy.com") {
+            let data = klipy::get_klipy_gif_url_from_url(&cx.assyst().reqwest_client, &url)
+                .await
+                .or(Err(TagParseError::MediaDownloadFail))?;
+
+            url = data;
+        }
+
+        Ok(Self(url))
+    }
 }
 
 impl Display for ImageUrl {
@@ -948,26 +955,8 @@ impl ParseArgument for ImageUrl {
             Err(TagParseError::NoImageFound)
         }
 
-        let ImageUrl(mut url) = combined_parsers(ctxt, label).await?;
-
-        // tenor urls only typically return a png, so this code visits the url
-        // and extracts the appropriate GIF url from the page.
-        if url.starts_with("https://tenor.com/view") {
-            let page = ctxt.cx.assyst().reqwest_client.get(&url).send().await?.text().await?;
-
-            let gif_url = regex::TENOR_GIF.find(&page).ok_or(TagParseError::MediaDownloadFail)?;
-            url = gif_url.as_str().to_owned();
-        }
-
-        if url.starts_with("https://klipy.com") {
-            let data = klipy::get_klipy_gif_url_from_url(&ctxt.cx.assyst().reqwest_client, &url)
-                .await
-                .or(Err(TagParseError::MediaDownloadFail))?;
-
-            url = data;
-        }
-
-        Ok(Self(url))
+        let url = combined_parsers(ctxt, label).await?;
+        url.resolve(&ctxt.cx).await
     }
 
     async fn parse_command_option(
@@ -1023,26 +1012,8 @@ impl ParseArgument for ImageUrl {
             Err(TagParseError::NoImageFound)
         }
 
-        let ImageUrl(mut url) = combined_parsers(ctxt, label).await?;
-
-        // tenor urls only typically return a png, so this code visits the url
-        // and extracts the appropriate GIF url from the page.
-        if url.starts_with("https://tenor.com/view") {
-            let page = ctxt.cx.assyst().reqwest_client.get(&url).send().await?.text().await?;
-
-            let gif_url = regex::TENOR_GIF.find(&page).ok_or(TagParseError::MediaDownloadFail)?;
-            url = gif_url.as_str().to_owned();
-        }
-
-        if url.starts_with("https://klipy.com") {
-            let data = klipy::get_klipy_gif_url_from_url(&ctxt.cx.assyst().reqwest_client, &url)
-                .await
-                .or(Err(TagParseError::MediaDownloadFail))?;
-
-            url = data;
-        }
-
-        Ok(Self(url))
+        let url = combined_parsers(ctxt, label).await?;
+        url.resolve(&ctxt.cx).await
     }
 
     fn as_command_options(name: &str) -> Vec<CommandOption> {
@@ -1102,3 +1073,154 @@ impl ParseArgument for Image {
         ]
     }
 }
+
+/// Number of attachment options of an [`Images`] argument in interaction commands.
+const IMAGES_ATTACHMENT_OPTIONS: usize = 4;
+
+/// Several images, for commands that combine them.
+///
+/// In raw messages, these are all attachments of the message, then any mentions, URLs and emojis
+/// given as arguments, then all attachments or the first embed of the replied-to message.
+/// In interaction commands, these are several attachment options and a list of links or mentions.
+pub struct Images(pub Vec<Vec<u8>>);
+
+impl Images {
+    async fn next_url_raw_message(
+        ctxt: &mut RawMessageParseCtxt<'_>,
+        label: Label,
+    ) -> Result<Option<ImageUrl>, TagParseError> {
+        macro_rules! handle {
+            ($v:expr) => {
+                match $v {
+                    Ok(r) => return Ok(Some(r)),
+                    Err(TagParseError::TwilightHttp(_)) => {},
+                    Err(err) if err.get_severity() == ErrorSeverity::High => return Err(err),
+                    _ => {},
+                }
+            };
+        }
+
+        handle!(commit_if_ok!(ctxt, ImageUrl::from_mention_raw_message, label));
+        handle!(commit_if_ok!(ctxt, ImageUrl::from_url_argument_raw_message, label));
+        handle!(commit_if_ok!(ctxt, ImageUrl::from_emoji_raw_message, label));
+        Ok(None)
+    }
+
+    async fn download(cx: &CommandCtxt<'_>, urls: Vec<ImageUrl>) -> Result<Self, TagParseError> {
+        if urls.is_empty() {
+            return Err(TagParseError::NoImageFound);
+        }
+        if urls.len() as u64 > MAX_INPUTS {
+            return Err(TagParseError::TooManyImages(MAX_INPUTS));
+        }
+
+        let mut images = Vec::with_capacity(urls.len());
+        for url in urls {
+            let ImageUrl(url) = url.resolve(cx).await?;
+            let data = downloader::download_content(
+                &cx.assyst().reqwest_client,
+                &url,
+                ABSOLUTE_INPUT_FILE_SIZE_LIMIT_BYTES,
+                true,
+            )
+            .await?;
+            images.push(data);
+        }
+
+        Ok(Images(images))
+    }
+}
+
+impl ParseArgument for Images {
+    async fn parse_raw_message(ctxt: &mut RawMessageParseCtxt<'_>, label: Label) -> Result<Self, TagParseError> {
+        let data = ctxt.cx.data;
+        let message = data.message.as_ref().unwrap();
+        let mut urls = message
+            .attachments
+            .iter()
+            .map(|a| ImageUrl(a.url.clone()))
+            .collect::<Vec<_>>();
+
+        while let Some(url) = Self::next_url_raw_message(ctxt, label.clone()).await? {
+            urls.push(url);
+        }
+
+        if let Some(reply) = message.referenced_message.as_deref() {
+            if reply.attachments.is_empty() {
+                urls.extend(ImageUrl::embed(reply.embeds.first()));
+            } else {
+                urls.extend(reply.attachments.iter().map(|a| ImageUrl(a.url.clone())));
+            }
+        }
+
+        Self::download(&ctxt.cx, urls).await
+    }
+
+    async fn parse_command_option(
+        ctxt: &mut InteractionCommandParseCtxt<'_>,
+        label: Label,
+    ) -> Result<Self, TagParseError> {
+        // if this is Some, this is a context menu command
+        // we must have our images defined here, instead of looking anywhere else
+        if let Some(ref r) = ctxt.cx.data.resolved_messages {
+            let urls = r
+                .first()
+                .map(|m| m.attachments.iter().map(|a| ImageUrl(a.url.clone())).collect())
+                .unwrap_or_default();
+
+            return Self::download(&ctxt.cx, urls).await;
+        }
+
+        let name = label.unwrap().0;
+        let mut urls = vec![];
+
+        for i in 1..=IMAGES_ATTACHMENT_OPTIONS {
+            if let Ok(option) = ctxt.option_by_name(&format!("{name}-attachment-{i}"))
+                && let CommandOptionValue::Attachment(id) = &option.value
+            {
+                urls.push(ImageUrl::attachment(ctxt.cx.data.interaction_attachments.get(id))?);
+            }
+        }
+
+        if let Ok(option) = ctxt.option_by_name(&format!("{name}-links")) {
+            let CommandOptionValue::String(links) = &option.value else {
+                return Err(TagParseError::MismatchedCommandOptionType((
+                    "String (links argument)".to_owned(),
+                    option.value.clone(),
+                )));
+            };
+
+            for word in links.split_ascii_whitespace() {
+                if regex::URL.is_match(word) {
+                    urls.push(ImageUrl(word.to_owned()));
+                } else {
+                    urls.push(ImageUrl::from_mention(&ctxt.cx, word).await?);
+                }
+            }
+        }
+
+        Self::download(&ctxt.cx, urls).await
+    }
+
+    fn as_command_options(name: &str) -> Vec<CommandOption> {
+        let mut options = (1..=IMAGES_ATTACHMENT_OPTIONS)
+            .map(|i| {
+                AttachmentBuilder::new(format!("{name}-attachment-{i}"), "attachment input")
+                    .required(false)
+                    .build()
+            })
+            .collect::<Vec<_>>();
+
+        options.push(
+            StringBuilder::new(format!("{name}-links"), "links or mentions, separated by spaces")
+                .required(false)
+                .build(),
+        );
+
+        options
+    }
+
+    fn usage(name: &str) -> String {
+        format!("<...{name}>")
+    }
+}
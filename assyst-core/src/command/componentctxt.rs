@@ -5,14 +5,16 @@ use moka::sync::Cache;
 use tokio::sync::Mutex;
 use twilight_model::application::interaction::message_component::MessageComponentInteractionData;
 use twilight_model::application::interaction::modal::ModalInteractionData;
-use twilight_model::channel::message::component::{Button, ButtonStyle};
+use twilight_model::channel::message::component::{Button, ButtonStyle, SelectMenu, SelectMenuOption, SelectMenuType};
 use twilight_model::channel::message::{AllowedMentions, Component, EmojiReactionType, MessageFlags};
 use twilight_model::http::interaction::{InteractionResponse, InteractionResponseType};
 use twilight_model::id::Id;
 use twilight_model::id::marker::{GuildMarker, InteractionMarker, UserMarker};
 use twilight_util::builder::InteractionResponseDataBuilder;
 
-use super::misc::tag::TagPaginatorComponentMetadata;
+use super::misc::tag::reports::TagReviewComponentMetadata;
+use super::misc::tag::restore::TagRestoreComponentMetadata;
+use super::misc::tag::{TagComponentsMetadata, TagPaginatorComponentMetadata};
 use crate::assyst::ThreadSafeAssyst;
 
 /// A register of all custom IDs that will trigger a certain component context callback.
@@ -52,6 +54,9 @@ impl ComponentCtxt {
         // further interactions
         let res = match &mut self.data {
             ComponentMetadata::TagList(tl) => tl.component_callback(component_data).await,
+            ComponentMetadata::TagComponents(tc) => tc.component_callback(component_data).await,
+            ComponentMetadata::TagRestore(tr) => tr.component_callback(component_data).await,
+            ComponentMetadata::TagReview(tr) => tr.component_callback(component_data).await,
         };
 
         if let Err(e) = res {
@@ -139,6 +144,9 @@ pub async fn respond_modal(
 #[derive(Clone)]
 pub enum ComponentMetadata {
     TagList(TagPaginatorComponentMetadata),
+    TagComponents(TagComponentsMetadata),
+    TagRestore(TagRestoreComponentMetadata),
+    TagReview(TagReviewComponentMetadata),
 }
 
 pub fn button_emoji_new(custom_id: &str, emoji: EmojiReactionType, style: ButtonStyle) -> Button {
@@ -167,6 +175,35 @@ pub fn button_new(custom_id: &str, label: &str, style: ButtonStyle) -> Button {
     }
 }
 
+/// Creates a text select menu where exactly one option can be picked.\
+/// Options are given as `(label, value)` pairs.
+pub fn select_menu_new(custom_id: &str, options: Vec<(String, String)>) -> SelectMenu {
+    SelectMenu {
+        id: None,
+        channel_types: None,
+        custom_id: custom_id.to_owned(),
+        default_values: None,
+        disabled: false,
+        kind: SelectMenuType::Text,
+        max_values: Some(1),
+        min_values: Some(1),
+        options: Some(
+            options
+                .into_iter()
+                .map(|(label, value)| SelectMenuOption {
+                    default: false,
+                    description: None,
+                    emoji: None,
+                    label,
+                    value,
+                })
+                .collect(),
+        ),
+        placeholder: None,
+        required: None,
+    }
+}
+
 /// Map of all existing component contexts.
 pub struct ComponentCtxts(Cache<String, Arc<Mutex<ComponentCtxt>>>);
 impl ComponentCtxts {
@@ -103,6 +103,7 @@ pub enum TagParseError {
     NoSticker,
     NoImageInHistory,
     NoImageFound,
+    TooManyImages(u64),
     MediaDownloadFail,
     InvalidSubcommand(String),
     NoInteractionSubcommandProvided,
@@ -178,6 +179,7 @@ impl Display for TagParseError {
             TagParseError::NoImageFound => {
                 f.write_str("an image was expected as an argument, but no image could be found")
             },
+            TagParseError::TooManyImages(max) => write!(f, "at most {max} images can be given"),
             TagParseError::MediaDownloadFail => f.write_str("failed to download media content"),
             TagParseError::InvalidSubcommand(name) => {
                 write!(f, "no subcommand found for given subcommand name {name}")
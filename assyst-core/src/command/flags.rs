@@ -1,6 +1,15 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 
-use anyhow::{bail, Context};
+use anyhow::{anyhow, bail, Context};
+use assyst_common::util::filetype::Type;
+use assyst_flux_iface::flux_request::{parse_output_format, OUTPUT_FORMATS};
+use twilight_model::application::command::CommandOption;
+use twilight_model::application::interaction::application_command::CommandOptionValue;
+use twilight_util::builder::command::StringBuilder;
+
+use super::errors::TagParseError;
+use super::{Category, CommandMetadata, InteractionCommandParseCtxt};
 
 #[macro_export]
 macro_rules! int_arg_u64 {
@@ -130,3 +139,93 @@ pub fn flags_from_str(input: &str, valid_flags: ValidFlags) -> anyhow::Result<Ha
 
     Ok(entries)
 }
+
+/// Name of the flag and interaction option that all image commands accept to choose the format of
+/// their output.
+pub const OUTPUT_FORMAT_FLAG: &str = "format";
+pub const OUTPUT_FORMAT_DESCRIPTION: &str = "Format of the output: gif, png, webp, mp4 or webm";
+
+fn unsupported_output_format(format: &str) -> TagParseError {
+    let supported = OUTPUT_FORMATS.map(|f| f.as_str()).join(", ");
+    TagParseError::FlagParseError(anyhow!("Unsupported output format: {format} (supported: {supported})"))
+}
+
+/// Takes the `--format` flag out of the arguments of an image command, so that it is not parsed as
+/// part of any other argument. The arguments of other commands are returned unchanged.
+///
+/// Fails if the flag is given to an image command whose output format can't be chosen.
+pub fn take_output_format<'a>(
+    metadata: &CommandMetadata,
+    input: &'a str,
+) -> Result<(Cow<'a, str>, Option<Type>), TagParseError> {
+    if metadata.category != Category::Image {
+        return Ok((Cow::Borrowed(input), None));
+    }
+
+    let flag = format!("--{OUTPUT_FORMAT_FLAG}");
+    let mut search_from = 0;
+
+    while let Some(start) = input[search_from..].find(&flag).map(|i| i + search_from) {
+        let rest = &input[start + flag.len()..];
+        search_from = start + flag.len();
+
+        // only match the flag as a whole word
+        let starts_word = input[..start].is_empty() || input[..start].ends_with(|c: char| c.is_ascii_whitespace());
+        let ends_word = rest.is_empty() || rest.starts_with(|c: char| c.is_ascii_whitespace());
+        if !starts_word || !ends_word {
+            continue;
+        }
+
+        if !metadata.output_format {
+            return Err(TagParseError::FlagParseError(anyhow!(
+                "The format of the output of this command can't be changed"
+            )));
+        }
+
+        let value = rest.split_ascii_whitespace().next().ok_or_else(|| {
+            TagParseError::FlagParseError(anyhow!(
+                "Flag {OUTPUT_FORMAT_FLAG} expects a value, but none was provided"
+            ))
+        })?;
+        let format = parse_output_format(value).ok_or_else(|| unsupported_output_format(value))?;
+
+        let end = input.len() - rest.trim_start().len() + value.len();
+        let remaining = format!("{} {}", input[..start].trim_end(), input[end..].trim_start());
+        return Ok((Cow::Owned(remaining.trim().to_owned()), Some(format)));
+    }
+
+    Ok((Cow::Borrowed(input), None))
+}
+
+/// Interaction option equivalent of the `--format` flag, added to all image commands.
+pub fn output_format_option() -> CommandOption {
+    StringBuilder::new(OUTPUT_FORMAT_FLAG, "format of the output")
+        .required(false)
+        .choices(OUTPUT_FORMATS.map(|f| (f.as_str(), f.as_str())))
+        .build()
+}
+
+/// Gets the output format chosen for an image command through [`output_format_option`], if any.
+pub fn output_format_from_option(
+    metadata: &CommandMetadata,
+    ctxt: &mut InteractionCommandParseCtxt<'_>,
+) -> Result<Option<Type>, TagParseError> {
+    if !metadata.output_format {
+        return Ok(None);
+    }
+
+    let Ok(option) = ctxt.option_by_name(OUTPUT_FORMAT_FLAG) else {
+        return Ok(None);
+    };
+
+    if let CommandOptionValue::String(format) = &option.value {
+        parse_output_format(format)
+            .map(Some)
+            .ok_or_else(|| unsupported_output_format(format))
+    } else {
+        Err(TagParseError::MismatchedCommandOptionType((
+            "String".to_owned(),
+            option.value.clone(),
+        )))
+    }
+}
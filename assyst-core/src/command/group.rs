@@ -82,7 +82,8 @@ macro_rules! define_commandgroup {
                         flag_descriptions: std::collections::HashMap::new(),
                         context_menu_message_command: $crate::defaults!(context_menu_message_command),
                         context_menu_user_command: $crate::defaults!(context_menu_user_command),
-                        group_parent_name: ""
+                        group_parent_name: "",
+                        output_format: false
                     })
                 }
 
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::Context;
+use assyst_proc_macro::command;
+use twilight_model::application::interaction::application_command::CommandOptionValue;
+use twilight_util::builder::command::IntegerBuilder;
+
+use crate::command::arguments::{Image, ParseArgument};
+use crate::command::errors::TagParseError;
+use crate::command::flags::{flags_from_str, FlagDecode, FlagType};
+use crate::command::{Availability, Category, CommandCtxt};
+use crate::int_arg_u64_opt;
+
+#[derive(Default)]
+pub struct AssembleFlags {
+    pub delay: Option<u64>,
+    pub loops: Option<i64>,
+}
+impl FlagDecode for AssembleFlags {
+    fn from_str(input: &str) -> anyhow::Result<Self> {
+        let mut valid_flags = HashMap::new();
+        valid_flags.insert("delay", FlagType::WithValue);
+        valid_flags.insert("loops", FlagType::WithValue);
+
+        let raw_decode = flags_from_str(input, valid_flags)?;
+        let result = Self {
+            delay: raw_decode
+                .get("delay")
+                .unwrap_or(&None)
+                .clone()
+                .map(|x| x.parse().context("Provided delay is invalid"))
+                .transpose()?,
+            loops: raw_decode
+                .get("loops")
+                .unwrap_or(&None)
+                .clone()
+                .map(|x| x.parse().context("Provided loop count is invalid"))
+                .transpose()?,
+        };
+
+        Ok(result)
+    }
+}
+impl ParseArgument for AssembleFlags {
+    fn as_command_options(_: &str) -> Vec<twilight_model::application::command::CommandOption> {
+        vec![
+            IntegerBuilder::new("delay", "time between frames in milliseconds")
+                .required(false)
+                .build(),
+            IntegerBuilder::new("loops", "number of times to loop (-1 for infinite)")
+                .required(false)
+                .build(),
+        ]
+    }
+
+    async fn parse_raw_message(
+        ctxt: &mut crate::command::RawMessageParseCtxt<'_>,
+        label: crate::command::Label,
+    ) -> Result<Self, crate::command::errors::TagParseError> {
+        let args = ctxt.rest_all(label);
+        let parsed = Self::from_str(&args).map_err(TagParseError::FlagParseError)?;
+        Ok(parsed)
+    }
+
+    async fn parse_command_option(
+        ctxt: &mut crate::command::InteractionCommandParseCtxt<'_>,
+        _: crate::command::Label,
+    ) -> Result<Self, TagParseError> {
+        let delay = int_arg_u64_opt!(ctxt, "delay");
+        let loops = match ctxt.option_by_name("loops").map(|o| &o.value) {
+            Ok(CommandOptionValue::Integer(loops)) => Some(*loops),
+            _ => None,
+        };
+
+        Ok(Self { delay, loops })
+    }
+}
+
+#[command(
+    description = "build a gif or video out of a zip of frames, like the ones from the frames command",
+    aliases = ["unframes"],
+    cooldown = Duration::from_secs(4),
+    access = Availability::Public,
+    category = Category::Image,
+    usage = "[zip] <flags>",
+    examples = ["https://link.to.my/frames.zip", "https://link.to.my/frames.zip --delay 50 --loops -1 --format mp4"],
+    send_processing = true,
+    flag_descriptions = [
+        ("delay", "Time between frames in milliseconds"),
+        ("loops", "Number of times to loop, -1 to loop forever"),
+    ]
+)]
+pub async fn assemble(ctxt: CommandCtxt<'_>, source: Image, flags: AssembleFlags) -> anyhow::Result<()> {
+    let result = ctxt
+        .flux_handler()
+        .assemble(
+            source.0,
+            flags.delay,
+            flags.loops,
+            ctxt.data.author.id.get(),
+            ctxt.data.guild_id.map(twilight_model::id::Id::get),
+        )
+        .await?;
+
+    ctxt.reply(result).await?;
+
+    Ok(())
+}
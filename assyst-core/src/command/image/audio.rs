@@ -5,6 +5,32 @@ use assyst_proc_macro::command;
 use crate::command::arguments::Image;
 use crate::command::{Availability, Category, CommandCtxt};
 
+#[command(
+    description = "boost the bass of a video or audio file",
+    aliases = ["bass"],
+    cooldown = Duration::from_secs(3),
+    access = Availability::Public,
+    category = Category::Audio,
+    usage = "[video|audio] <gain>",
+    examples = ["https://link.to.my/video.mp4", "https://link.to.my/video.mp4 20"],
+    send_processing = true
+)]
+pub async fn bassboost(ctxt: CommandCtxt<'_>, source: Image, gain: Option<f64>) -> anyhow::Result<()> {
+    let result = ctxt
+        .flux_handler()
+        .bass_boost(
+            source.0,
+            gain,
+            ctxt.data.author.id.get(),
+            ctxt.data.guild_id.map(twilight_model::id::Id::get),
+        )
+        .await?;
+
+    ctxt.reply(result).await?;
+
+    Ok(())
+}
+
 #[command(
     description = "give an image drip",
     cooldown = Duration::from_secs(3),
@@ -25,6 +51,27 @@ pub async fn drip(ctxt: CommandCtxt<'_>, source: Image) -> anyhow::Result<()> {
     Ok(())
 }
 
+#[command(
+    description = "get the audio of a video as an mp3",
+    aliases = ["getaudio"],
+    cooldown = Duration::from_secs(3),
+    access = Availability::Public,
+    category = Category::Audio,
+    usage = "[video|audio]",
+    examples = ["https://link.to.my/video.mp4"],
+    send_processing = true
+)]
+pub async fn extractaudio(ctxt: CommandCtxt<'_>, source: Image) -> anyhow::Result<()> {
+    let result = ctxt
+        .flux_handler()
+        .extract_audio(source.0, ctxt.data.author.id.get(), ctxt.data.guild_id.map(twilight_model::id::Id::get))
+        .await?;
+
+    ctxt.reply(result).await?;
+
+    Ok(())
+}
+
 #[command(
     description = "femurbreaker over image",
     cooldown = Duration::from_secs(3),
@@ -45,6 +92,92 @@ pub async fn femurbreaker(ctxt: CommandCtxt<'_>, source: Image) -> anyhow::Resul
     Ok(())
 }
 
+#[command(
+    description = "remove the audio of a video",
+    aliases = ["mute"],
+    cooldown = Duration::from_secs(3),
+    access = Availability::Public,
+    category = Category::Audio,
+    usage = "[video|audio]",
+    examples = ["https://link.to.my/video.mp4"],
+    send_processing = true
+)]
+pub async fn muteaudio(ctxt: CommandCtxt<'_>, source: Image) -> anyhow::Result<()> {
+    let result = ctxt
+        .flux_handler()
+        .mute_audio(source.0, ctxt.data.author.id.get(), ctxt.data.guild_id.map(twilight_model::id::Id::get))
+        .await?;
+
+    ctxt.reply(result).await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "speed up a video or audio file and raise its pitch",
+    cooldown = Duration::from_secs(3),
+    access = Availability::Public,
+    category = Category::Audio,
+    usage = "[video|audio]",
+    examples = ["https://link.to.my/video.mp4"],
+    send_processing = true
+)]
+pub async fn nightcore(ctxt: CommandCtxt<'_>, source: Image) -> anyhow::Result<()> {
+    let result = ctxt
+        .flux_handler()
+        .nightcore(source.0, ctxt.data.author.id.get(), ctxt.data.guild_id.map(twilight_model::id::Id::get))
+        .await?;
+
+    ctxt.reply(result).await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "shift the pitch of a video or audio file without changing its speed",
+    cooldown = Duration::from_secs(3),
+    access = Availability::Public,
+    category = Category::Audio,
+    usage = "[video|audio] <semitones>",
+    examples = ["https://link.to.my/video.mp4 5", "https://link.to.my/video.mp4 -3"],
+    send_processing = true
+)]
+pub async fn pitch(ctxt: CommandCtxt<'_>, source: Image, semitones: f64) -> anyhow::Result<()> {
+    let result = ctxt
+        .flux_handler()
+        .pitch(
+            source.0,
+            semitones,
+            ctxt.data.author.id.get(),
+            ctxt.data.guild_id.map(twilight_model::id::Id::get),
+        )
+        .await?;
+
+    ctxt.reply(result).await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "add reverb to a video or audio file",
+    cooldown = Duration::from_secs(3),
+    access = Availability::Public,
+    category = Category::Audio,
+    usage = "[video|audio]",
+    examples = ["https://link.to.my/video.mp4"],
+    send_processing = true
+)]
+pub async fn reverb(ctxt: CommandCtxt<'_>, source: Image) -> anyhow::Result<()> {
+    let result = ctxt
+        .flux_handler()
+        .reverb(source.0, ctxt.data.author.id.get(), ctxt.data.guild_id.map(twilight_model::id::Id::get))
+        .await?;
+
+    ctxt.reply(result).await?;
+
+    Ok(())
+}
+
 #[command(
     description = "⚠️ alert ⚠️",
     cooldown = Duration::from_secs(3),
@@ -104,3 +237,28 @@ pub async fn terraria(ctxt: CommandCtxt<'_>, source: Image) -> anyhow::Result<()
 
     Ok(())
 }
+
+#[command(
+    description = "change the volume of a video or audio file",
+    cooldown = Duration::from_secs(3),
+    access = Availability::Public,
+    category = Category::Audio,
+    usage = "[video|audio] <multiplier>",
+    examples = ["https://link.to.my/video.mp4 2", "https://link.to.my/video.mp4 0.5"],
+    send_processing = true
+)]
+pub async fn volume(ctxt: CommandCtxt<'_>, source: Image, multiplier: f64) -> anyhow::Result<()> {
+    let result = ctxt
+        .flux_handler()
+        .volume(
+            source.0,
+            multiplier,
+            ctxt.data.author.id.get(),
+            ctxt.data.guild_id.map(twilight_model::id::Id::get),
+        )
+        .await?;
+
+    ctxt.reply(result).await?;
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use assyst_flux_iface::chain::parse_chain;
+use assyst_proc_macro::command;
+
+use crate::command::arguments::{Image, Rest};
+use crate::command::{Availability, Category, CommandCtxt};
+
+#[command(
+    description = "apply several effects to an image, one after the other",
+    aliases = ["flux", "pipeline"],
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Image,
+    usage = "[image] [effect <options...> | effect <options...> | ...]",
+    examples = ["https://link.to.my/image.png caption \"hi\" | speed 2 | reverse", "https://link.to.my/image.png blur 3 | rotate 90 | jpeg 5"],
+    send_processing = true
+)]
+pub async fn chain(ctxt: CommandCtxt<'_>, source: Image, effects: Rest) -> anyhow::Result<()> {
+    let steps = parse_chain(&effects.0)?;
+    let names = steps.iter().map(|(name, _)| format!("`{name}`")).collect::<Vec<_>>();
+
+    let result = ctxt
+        .flux_handler()
+        .chain(
+            source.0,
+            steps,
+            ctxt.data.author.id.get(),
+            ctxt.data.guild_id.map(twilight_model::id::Id::get),
+        )
+        .await?;
+
+    ctxt.reply((result, &format!("Applied effects: {}", names.join(" → "))[..]))
+        .await?;
+
+    Ok(())
+}
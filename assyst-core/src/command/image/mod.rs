@@ -11,12 +11,16 @@ use super::arguments::{Image, RestNoFlags, Word};
 use super::messagebuilder::{Attachment, MessageBuilder};
 use crate::command::{Availability, Category, CommandCtxt};
 
+pub mod assemble;
 pub mod audio;
 pub mod bloom;
 pub mod caption;
+pub mod chain;
 pub mod makesweet;
+pub mod multi;
 pub mod randomize;
 pub mod speechbubble;
+pub mod video;
 
 #[command(
     description = "ah shit here we go again",
@@ -171,7 +175,8 @@ pub async fn flop(ctxt: CommandCtxt<'_>, source: Image) -> anyhow::Result<()> {
     category = Category::Image,
     usage = "[image]",
     examples = ["https://link.to.my/image.png"],
-    send_processing = true
+    send_processing = true,
+    output_format = false
 )]
 pub async fn frames(ctxt: CommandCtxt<'_>, source: Image) -> anyhow::Result<()> {
     let result = ctxt
@@ -330,10 +335,18 @@ pub async fn grayscale(ctxt: CommandCtxt<'_>, source: Image) -> anyhow::Result<(
     usage = "[image]",
     examples = ["https://link.to.my/image.png"],
     send_processing = true,
-    context_menu_message_command = "Image Information"
+    context_menu_message_command = "Image Information",
+    output_format = false
 )]
 pub async fn imageinfo(ctxt: CommandCtxt<'_>, source: Image) -> anyhow::Result<()> {
-    let result = ctxt.flux_handler().image_info(source.0).await?;
+    let result = ctxt
+        .flux_handler()
+        .image_info(
+            source.0,
+            ctxt.data.author.id.get(),
+            ctxt.data.guild_id.map(twilight_model::id::Id::get),
+        )
+        .await?;
 
     if let MediaInfo::Image(i) = result {
         let mut kv = Vec::new();
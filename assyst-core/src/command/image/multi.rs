@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use anyhow::bail;
+use assyst_proc_macro::command;
+
+use crate::command::arguments::Images;
+use crate::command::{Availability, Category, CommandCtxt};
+
+#[command(
+    description = "arrange several images into a collage",
+    cooldown = Duration::from_secs(4),
+    access = Availability::Public,
+    category = Category::Image,
+    usage = "[images...]",
+    examples = ["https://link.to.my/image.png https://link.to.my/image2.png https://link.to.my/image3.png"],
+    send_processing = true
+)]
+pub async fn collage(ctxt: CommandCtxt<'_>, sources: Images) -> anyhow::Result<()> {
+    let result = ctxt
+        .flux_handler()
+        .collage(
+            sources.0,
+            ctxt.data.author.id.get(),
+            ctxt.data.guild_id.map(twilight_model::id::Id::get),
+        )
+        .await?;
+
+    ctxt.reply(result).await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "overlay several images on top of each other to compare them",
+    cooldown = Duration::from_secs(4),
+    access = Availability::Public,
+    category = Category::Image,
+    usage = "[images...]",
+    examples = ["https://link.to.my/image.png https://link.to.my/image2.png"],
+    send_processing = true
+)]
+pub async fn compare(ctxt: CommandCtxt<'_>, sources: Images) -> anyhow::Result<()> {
+    let result = ctxt
+        .flux_handler()
+        .compare(
+            sources.0,
+            ctxt.data.author.id.get(),
+            ctxt.data.guild_id.map(twilight_model::id::Id::get),
+        )
+        .await?;
+
+    ctxt.reply(result).await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "arrange several images into a grid",
+    cooldown = Duration::from_secs(4),
+    access = Availability::Public,
+    category = Category::Image,
+    usage = "[images...] <columns>",
+    examples = ["https://link.to.my/image.png https://link.to.my/image2.png https://link.to.my/image3.png 3"],
+    send_processing = true
+)]
+pub async fn grid(ctxt: CommandCtxt<'_>, sources: Images, columns: Option<u64>) -> anyhow::Result<()> {
+    if columns == Some(0) {
+        bail!("A grid needs at least 1 column.");
+    }
+
+    let result = ctxt
+        .flux_handler()
+        .grid(
+            sources.0,
+            columns,
+            ctxt.data.author.id.get(),
+            ctxt.data.guild_id.map(twilight_model::id::Id::get),
+        )
+        .await?;
+
+    ctxt.reply(result).await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "place several images side by side",
+    aliases = ["sidebyside"],
+    cooldown = Duration::from_secs(4),
+    access = Availability::Public,
+    category = Category::Image,
+    usage = "[images...]",
+    examples = ["https://link.to.my/image.png https://link.to.my/image2.png"],
+    send_processing = true
+)]
+pub async fn hstack(ctxt: CommandCtxt<'_>, sources: Images) -> anyhow::Result<()> {
+    let result = ctxt
+        .flux_handler()
+        .hstack(
+            sources.0,
+            ctxt.data.author.id.get(),
+            ctxt.data.guild_id.map(twilight_model::id::Id::get),
+        )
+        .await?;
+
+    ctxt.reply(result).await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "place several images on top of each other",
+    cooldown = Duration::from_secs(4),
+    access = Availability::Public,
+    category = Category::Image,
+    usage = "[images...]",
+    examples = ["https://link.to.my/image.png https://link.to.my/image2.png"],
+    send_processing = true
+)]
+pub async fn vstack(ctxt: CommandCtxt<'_>, sources: Images) -> anyhow::Result<()> {
+    let result = ctxt
+        .flux_handler()
+        .vstack(
+            sources.0,
+            ctxt.data.author.id.get(),
+            ctxt.data.guild_id.map(twilight_model::id::Id::get),
+        )
+        .await?;
+
+    ctxt.reply(result).await?;
+
+    Ok(())
+}
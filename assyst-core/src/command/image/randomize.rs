@@ -67,7 +67,7 @@ pub async fn randomize(ctxt: CommandCtxt<'_>, source: Image, count: Option<u64>)
     let result = ctxt
         .assyst()
         .flux_handler
-        .run_flux(request, limits.time)
+        .run_flux_queued(request, &limits, ctxt.data.author.id.get())
         .await
         .context(format!("Applied effects: {}", effects.join(", ")))?;
 
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use assyst_proc_macro::command;
+
+use crate::command::arguments::{Image, Images, Time};
+use crate::command::{Availability, Category, CommandCtxt};
+
+#[command(
+    description = "join several videos together, one after another",
+    cooldown = Duration::from_secs(4),
+    access = Availability::Public,
+    category = Category::Image,
+    usage = "[videos...]",
+    examples = ["https://link.to.my/video.mp4 https://link.to.my/video2.mp4"],
+    send_processing = true
+)]
+pub async fn concat(ctxt: CommandCtxt<'_>, sources: Images) -> anyhow::Result<()> {
+    let result = ctxt
+        .flux_handler()
+        .concat(
+            sources.0,
+            ctxt.data.author.id.get(),
+            ctxt.data.guild_id.map(twilight_model::id::Id::get),
+        )
+        .await?;
+
+    ctxt.reply(result).await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "remove the part of a video between two timestamps",
+    cooldown = Duration::from_secs(3),
+    access = Availability::Public,
+    category = Category::Image,
+    usage = "[video] <start> <end>",
+    examples = ["https://link.to.my/video.mp4 5s 10s", "https://link.to.my/video.mp4 1m 1m30s"],
+    send_processing = true
+)]
+pub async fn cut(ctxt: CommandCtxt<'_>, source: Image, start: Time, end: Time) -> anyhow::Result<()> {
+    let result = ctxt
+        .flux_handler()
+        .cut(
+            source.0,
+            Duration::from_millis(start.millis),
+            Duration::from_millis(end.millis),
+            ctxt.data.author.id.get(),
+            ctxt.data.guild_id.map(twilight_model::id::Id::get),
+        )
+        .await?;
+
+    ctxt.reply(result).await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "keep only the part of a video between two timestamps",
+    aliases = ["clip"],
+    cooldown = Duration::from_secs(3),
+    access = Availability::Public,
+    category = Category::Image,
+    usage = "[video] <start> <end>",
+    examples = ["https://link.to.my/video.mp4 5s 10s", "https://link.to.my/video.mp4 0s 1m"],
+    send_processing = true
+)]
+pub async fn trim(ctxt: CommandCtxt<'_>, source: Image, start: Time, end: Time) -> anyhow::Result<()> {
+    let result = ctxt
+        .flux_handler()
+        .trim(
+            source.0,
+            Duration::from_millis(start.millis),
+            Duration::from_millis(end.millis),
+            ctxt.data.author.id.get(),
+            ctxt.data.guild_id.map(twilight_model::id::Id::get),
+        )
+        .await?;
+
+    ctxt.reply(result).await?;
+
+    Ok(())
+}
@@ -0,0 +1,297 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use anyhow::{Context, bail, ensure};
+use assyst_common::util::unix_timestamp;
+use assyst_database::model::public_tag::PublicTag;
+use assyst_database::model::tag::Tag;
+use assyst_database::model::tag_alias::TagAlias;
+use assyst_database::model::tag_install::TagInstall;
+use assyst_proc_macro::command;
+use assyst_string_fmt::Markdown;
+
+use super::{DEFAULT_LIST_COUNT, RESERVED_NAMES, ensure_not_locked};
+use crate::command::arguments::{RestNoFlags, Word, WordAutocomplete};
+use crate::command::{Availability, Category, CommandCtxt};
+
+const MAX_PUBLIC_DESCRIPTION_LENGTH: usize = 100;
+
+#[command(
+    description = "publish a tag that you own to the public tag library",
+    cooldown = Duration::from_secs(5),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[name] [description]",
+    examples = ["script runs some javascript", "test a simple test tag"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn publish(
+    ctxt: CommandCtxt<'_>,
+    #[autocomplete = "crate::command::misc::tag::tag_names_autocomplete_for_user"] name: WordAutocomplete,
+    description: RestNoFlags,
+) -> anyhow::Result<()> {
+    let author = ctxt.data.author.id.get();
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tags can only be published from guilds.")
+    };
+
+    ensure!(
+        description.0.chars().count() <= MAX_PUBLIC_DESCRIPTION_LENGTH,
+        "Descriptions cannot exceed {MAX_PUBLIC_DESCRIPTION_LENGTH} characters."
+    );
+
+    let tag = Tag::get(
+        &ctxt.assyst().database_handler,
+        guild_id.get() as i64,
+        &name.0.to_ascii_lowercase(),
+    )
+    .await?
+    .context("Tag not found in this server.")?;
+
+    ensure!(tag.author == author as i64, "You can only publish tags that you own.");
+
+    let public = PublicTag::publish(
+        &ctxt.assyst().database_handler,
+        &tag.name,
+        &description.0,
+        &tag.data,
+        author as i64,
+        unix_timestamp() as i64,
+    )
+    .await
+    .context("Failed to publish tag")?;
+
+    ctxt.reply(format!(
+        "Published tag {} to the library with ID {} (version {}). Other servers can install it with `{}t install {}`",
+        public.name.codestring(),
+        public.id,
+        public.version,
+        ctxt.data.calling_prefix,
+        public.id
+    ))
+    .await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "remove a tag that you published from the public tag library",
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[library id]",
+    examples = ["42"],
+    group_parent_name = "tag"
+)]
+pub async fn unpublish(ctxt: CommandCtxt<'_>, id: u64) -> anyhow::Result<()> {
+    let success = PublicTag::delete(
+        &ctxt.assyst().database_handler,
+        id as i64,
+        ctxt.data.author.id.get() as i64,
+    )
+    .await
+    .context("Failed to unpublish tag")?;
+
+    ensure!(
+        success,
+        "Failed to unpublish that tag. Does it exist, and did you publish it?"
+    );
+
+    ctxt.reply(format!("Removed tag {id} from the library.")).await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "search the public tag library",
+    aliases = ["lib"],
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[query]",
+    examples = ["script", "8ball"],
+    group_parent_name = "tag"
+)]
+pub async fn library(ctxt: CommandCtxt<'_>, query: RestNoFlags) -> anyhow::Result<()> {
+    let tags = PublicTag::search(
+        &ctxt.assyst().database_handler,
+        &query.0.to_ascii_lowercase(),
+        DEFAULT_LIST_COUNT,
+    )
+    .await
+    .context("Failed to search the tag library")?;
+
+    ensure!(!tags.is_empty(), "No public tags found for the requested filter");
+
+    let mut message = format!(
+        "🗒️ **Public tags matching {}**\nInstall a tag into this server by running `{}t install <id>`\n\n",
+        query.0.codestring(),
+        ctxt.data.calling_prefix
+    );
+
+    for tag in &tags {
+        writeln!(
+            message,
+            "`{}` {} v{} (<@{}>): {}",
+            tag.id, tag.name, tag.version, tag.author, tag.description
+        )?;
+    }
+
+    ctxt.reply(message).await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "install a tag from the public tag library into this server",
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[library id] <name>",
+    examples = ["42", "42 myscript"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn install(ctxt: CommandCtxt<'_>, id: u64, name: Option<Word>) -> anyhow::Result<()> {
+    let author = ctxt.data.author.id.get();
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tags can only be installed into guilds.")
+    };
+
+    let public = PublicTag::get(&ctxt.assyst().database_handler, id as i64)
+        .await
+        .context("Failed to fetch public tag")?
+        .context("That tag does not exist in the library.")?;
+
+    let name = name.map_or(public.name.clone(), |n| n.0.to_ascii_lowercase());
+
+    ensure!(name.len() < 20, "Tag names cannot exceed 20 characters.");
+    ensure!(
+        !RESERVED_NAMES.contains(&&name[..]),
+        "Tag names cannot be a reserved word."
+    );
+    ensure!(!name.contains(' '), "Tag names cannot contain spaces.");
+    ensure!(
+        TagAlias::get(&ctxt.assyst().database_handler, guild_id.get() as i64, &name)
+            .await?
+            .is_none(),
+        "That tag name is already used as an alias in this server."
+    );
+
+    let tag = Tag {
+        name,
+        guild_id: guild_id.get() as i64,
+        data: public.data,
+        author: author as i64,
+        created_at: unix_timestamp() as i64,
+    };
+
+    let success = tag
+        .set(&ctxt.assyst().database_handler)
+        .await
+        .context("Failed to create tag")?;
+
+    ensure!(
+        success,
+        "That tag name is already used in this server. Try installing it under a different name."
+    );
+
+    TagInstall {
+        guild_id: tag.guild_id,
+        name: tag.name.clone(),
+        public_id: public.id,
+        version: public.version,
+    }
+    .set(&ctxt.assyst().database_handler)
+    .await
+    .context("Failed to record tag install")?;
+
+    ctxt.reply(format!(
+        "Successfully installed tag {} (version {})",
+        tag.name.codestring(),
+        public.version
+    ))
+    .await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "update an installed tag to the latest version from the public tag library",
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[name]",
+    examples = ["script"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn update(
+    ctxt: CommandCtxt<'_>,
+    #[autocomplete = "crate::command::misc::tag::tag_names_autocomplete_for_user"] name: WordAutocomplete,
+) -> anyhow::Result<()> {
+    let author = ctxt.data.author.id.get();
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tags can only be updated in guilds.")
+    };
+
+    let tag = Tag::get(
+        &ctxt.assyst().database_handler,
+        guild_id.get() as i64,
+        &name.0.to_ascii_lowercase(),
+    )
+    .await?
+    .context("Tag not found in this server.")?;
+
+    let install = TagInstall::get(&ctxt.assyst().database_handler, tag.guild_id, &tag.name)
+        .await
+        .context("Failed to fetch tag install")?
+        .context("This tag was not installed from the public tag library.")?;
+
+    let public = PublicTag::get(&ctxt.assyst().database_handler, install.public_id)
+        .await
+        .context("Failed to fetch public tag")?
+        .context("This tag is no longer published in the library.")?;
+
+    ensure!(
+        public.version > install.version,
+        "This tag is already up to date (version {}).",
+        install.version
+    );
+
+    ensure_not_locked(ctxt.assyst(), tag.guild_id, &tag.name).await?;
+
+    let success = Tag::edit(
+        &ctxt.assyst().database_handler,
+        author as i64,
+        tag.guild_id,
+        &tag.name,
+        &public.data,
+    )
+    .await
+    .context("Failed to edit tag")?;
+
+    ensure!(
+        success,
+        "Failed to update that tag. Do you own it, or are you one of its collaborators?"
+    );
+
+    TagInstall {
+        version: public.version,
+        ..install
+    }
+    .set(&ctxt.assyst().database_handler)
+    .await
+    .context("Failed to record tag install")?;
+
+    ctxt.reply(format!(
+        "Updated tag {} from version {} to version {}",
+        tag.name.codestring(),
+        install.version,
+        public.version
+    ))
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,2140 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io::{Cursor, Write as IoWrite};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, anyhow, bail, ensure};
+use assyst_common::err;
+use assyst_common::util::discord::{format_discord_timestamp, format_tag, get_avatar_url};
+use assyst_common::util::table::generate_list_fixed_delim;
+use assyst_common::util::{string_from_likely_utf8, unix_timestamp};
+use assyst_database::model::tag::{Tag, TagSearchResult};
+use assyst_database::model::tag_alias::TagAlias;
+use assyst_database::model::tag_collaborator::TagCollaborator;
+use assyst_database::model::tag_lock::TagLock;
+use assyst_database::model::tag_usage::{TagUsage, TagUsageStats};
+use assyst_database::model::user_tag::UserTag;
+use assyst_proc_macro::command;
+use assyst_string_fmt::{Ansi, Markdown};
+use assyst_tag::ParseResult;
+use assyst_tag::errors::{Diagnostic, ErrorKind, TResult};
+use assyst_tag::parser::{ParseMode, TagComponent, limits};
+use serde::{Deserialize, Serialize};
+use tokio::runtime::Handle;
+use tokio::sync::Mutex;
+use twilight_model::application::interaction::modal::{ModalInteractionActionRow, ModalInteractionComponent};
+use twilight_model::channel::Message;
+use twilight_model::channel::message::component::{ActionRow, ButtonStyle, TextInput, TextInputStyle};
+use twilight_model::channel::message::{Component, EmojiReactionType};
+use twilight_model::http::attachment::Attachment as TwilightAttachment;
+use twilight_model::http::interaction::{InteractionResponse, InteractionResponseType};
+use twilight_model::id::Id;
+use twilight_model::id::marker::{ChannelMarker, EmojiMarker, UserMarker};
+use twilight_util::builder::command::IntegerBuilder;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use self::library::{install_command, library_command, publish_command, unpublish_command, update_command};
+use self::personal::personal_command;
+use self::reports::{report_command, reports_command, unlock_command};
+use self::restore::restore_command;
+use self::revisions::{diff_command, history_command, rollback_command};
+use super::CommandCtxt;
+use crate::assyst::ThreadSafeAssyst;
+use crate::command::arguments::{Image, ImageUrl, ParseArgument, RestNoFlags, User, Word, WordAutocomplete};
+use crate::command::autocomplete::AutocompleteData;
+use crate::command::componentctxt::{
+    ComponentCtxt, ComponentCtxtRegister, ComponentInteractionData, ComponentMetadata, button_emoji_new, button_new,
+    respond_modal, respond_update_text, select_menu_new,
+};
+use crate::command::errors::TagParseError;
+use crate::command::flags::{FlagDecode, FlagType, flags_from_str};
+use crate::command::messagebuilder::{Attachment, MessageBuilder};
+use crate::command::{Availability, Category};
+use crate::downloader::{ABSOLUTE_INPUT_FILE_SIZE_LIMIT_BYTES, download_content};
+use crate::rest::eval::fake_eval;
+use crate::{define_commandgroup, int_arg_u64};
+
+pub mod library;
+pub mod personal;
+pub mod reports;
+pub mod restore;
+pub mod revisions;
+
+const DEFAULT_LIST_COUNT: i64 = 15;
+const MAX_SEARCH_SNIPPET_LENGTH: usize = 60;
+const TOP_TAGS_COUNT: i64 = 20;
+const RESERVED_NAMES: &[&str] = &[
+    "create",
+    "add",
+    "edit",
+    "raw",
+    "remove",
+    "delete",
+    "list",
+    "info",
+    "history",
+    "diff",
+    "rollback",
+    "transfer",
+    "collaborators",
+    "collaborator",
+    "collab",
+    "alias",
+    "publish",
+    "unpublish",
+    "library",
+    "lib",
+    "install",
+    "update",
+    "top",
+    "restore",
+    "personal",
+    "me",
+    "report",
+    "reports",
+    "unlock",
+];
+const MAX_SHOWN_DIAGNOSTICS: usize = 3;
+
+#[command(
+    description = "create a tag",
+    aliases = ["add"],
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[name] [contents]",
+    examples = ["test hello", "script 1+2 is: {js:1+2}"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn create(ctxt: CommandCtxt<'_>, name: Word, contents: RestNoFlags) -> anyhow::Result<()> {
+    let author = ctxt.data.author.id.get();
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tags can only be created in guilds.")
+    };
+
+    ensure!(name.0.len() < 20, "Tag names cannot exceed 20 characters.");
+    ensure!(
+        !RESERVED_NAMES.contains(&&name.0[..]),
+        "Tag names cannot be a reserved word."
+    );
+    ensure!(!name.0.contains(' '), "Tag names cannot contain spaces.");
+    ensure!(
+        TagAlias::get(
+            &ctxt.assyst().database_handler,
+            guild_id.get() as i64,
+            &name.0.to_ascii_lowercase()
+        )
+        .await?
+        .is_none(),
+        "That tag name is already used as an alias in this server."
+    );
+
+    let tag = Tag {
+        name: name.0.to_ascii_lowercase(),
+        guild_id: guild_id.get() as i64,
+        data: contents.0,
+        author: author as i64,
+        created_at: unix_timestamp() as i64,
+    };
+
+    let success = tag
+        .set(&ctxt.assyst().database_handler)
+        .await
+        .context("Failed to create tag")?;
+
+    ensure!(success, "That tag name is already used in this server.");
+
+    ctxt.reply(format!(
+        "Successfully created tag {}",
+        tag.name.to_ascii_lowercase().codestring()
+    ))
+    .await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "edit a tag that you own or collaborate on",
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[name] [contents]",
+    examples = ["test hello there", "script 2+2 is: {js:2+2}"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn edit(
+    ctxt: CommandCtxt<'_>,
+    #[autocomplete = "crate::command::misc::tag::tag_names_autocomplete_for_user"] name: WordAutocomplete,
+    contents: RestNoFlags,
+) -> anyhow::Result<()> {
+    let author = ctxt.data.author.id.get();
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tags can only be edited in guilds.")
+    };
+
+    let tag = Tag::get(
+        &ctxt.assyst().database_handler,
+        guild_id.get() as i64,
+        &name.0.to_ascii_lowercase(),
+    )
+    .await?
+    .context("Tag not found in this server.")?;
+
+    ensure_not_locked(ctxt.assyst(), tag.guild_id, &tag.name).await?;
+
+    let success = Tag::edit(
+        &ctxt.assyst().database_handler,
+        author as i64,
+        guild_id.get() as i64,
+        &tag.name,
+        &contents.0,
+    )
+    .await
+    .context("Failed to edit tag")?;
+
+    ensure!(
+        success,
+        "Failed to edit that tag. Do you own it, or are you one of its collaborators?"
+    );
+
+    ctxt.reply(format!("Successfully edited tag {}", tag.name.codestring()))
+        .await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "delete a tag that you own (server managers can delete any tag in the server)",
+    aliases = ["remove"],
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[name]",
+    examples = ["test", "script"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn delete(
+    ctxt: CommandCtxt<'_>,
+    #[autocomplete = "crate::command::misc::tag::tag_names_autocomplete"] name: WordAutocomplete,
+) -> anyhow::Result<()> {
+    let author = ctxt.data.author.id.get();
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tags can only be deleted in guilds.")
+    };
+
+    let is_manager = ctxt
+        .assyst()
+        .rest_cache_handler
+        .user_is_guild_manager(guild_id.get(), author)
+        .await
+        .context("Failed to fetch user permissions")?;
+
+    if let Some(alias) = TagAlias::get(
+        &ctxt.assyst().database_handler,
+        guild_id.get() as i64,
+        &name.0.to_ascii_lowercase(),
+    )
+    .await?
+    {
+        ensure!(
+            is_manager || alias.author == author as i64,
+            "Failed to delete that alias. Do you own it?"
+        );
+
+        TagAlias::delete(&ctxt.assyst().database_handler, alias.guild_id, &alias.alias)
+            .await
+            .context("Failed to delete alias")?;
+
+        ctxt.reply(format!("Successfully deleted alias {}", alias.alias.codestring()))
+            .await?;
+
+        return Ok(());
+    }
+
+    let tag = Tag::get(
+        &ctxt.assyst().database_handler,
+        guild_id.get() as i64,
+        &name.0.to_ascii_lowercase(),
+    )
+    .await?
+    .context("Tag not found in this server.")?;
+
+    let success = if is_manager {
+        Tag::delete_force(&ctxt.assyst().database_handler, &tag.name, tag.guild_id, author as i64)
+            .await
+            .context("Failed to delete tag")?
+    } else {
+        ensure_not_locked(ctxt.assyst(), tag.guild_id, &tag.name).await?;
+
+        Tag::delete(&ctxt.assyst().database_handler, &tag.name, tag.guild_id, author as i64)
+            .await
+            .context("Failed to delete tag")?
+    };
+
+    ensure!(success, "Failed to delete that tag. Do you own it?");
+
+    ctxt.reply(format!(
+        "Successfully deleted tag {}",
+        name.0.to_ascii_lowercase().codestring()
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Used for both listing and searching tags
+#[derive(Clone, Debug)]
+pub struct TagPaginatorComponentMetadata {
+    pub current_page: u64,
+    pub page_next_cid: String,
+    pub page_prev_cid: String,
+    pub page_jump_cid: String,
+    pub jump_modal_cid: String,
+    pub jump_modal_text_cid: String,
+    pub invocating_user_id: Id<UserMarker>,
+    pub target_user_id: Option<Id<UserMarker>>,
+    pub tag_count: u64,
+    pub calling_prefix: String,
+    pub search_criteria: Option<String>,
+}
+impl TagPaginatorComponentMetadata {
+    pub async fn component_callback(&mut self, data: &ComponentInteractionData) -> anyhow::Result<()> {
+        if data.invocation_user_id != self.invocating_user_id {
+            bail!("This command was not ran by you.");
+        }
+
+        let pages = (self.tag_count as f64 / DEFAULT_LIST_COUNT as f64).ceil() as i64;
+
+        // respond with modal to jump to page
+        if data.custom_id == self.page_jump_cid {
+            let pages = (self.tag_count as f64 / DEFAULT_LIST_COUNT as f64).ceil() as i64;
+            let pages_digits = pages.to_string().len();
+            respond_modal(
+                data.assyst.clone(),
+                data.interaction_id,
+                &data.interaction_token,
+                "Jump to page",
+                vec![Component::ActionRow(ActionRow {
+                    id: None,
+                    components: vec![Component::TextInput(TextInput {
+                        id: None,
+                        custom_id: self.jump_modal_text_cid.clone(),
+                        label: Some("Page".to_string()),
+                        max_length: Some(pages_digits as u16),
+                        min_length: Some(1),
+                        placeholder: None,
+                        required: Some(true),
+                        style: TextInputStyle::Short,
+                        value: None,
+                    })],
+                })],
+                &self.jump_modal_cid,
+            )
+            .await?;
+
+            return Ok(());
+        } else if data.custom_id == self.jump_modal_cid {
+            let modal = data.modal_submit_interaction_data.clone().context("No modal data??")?;
+            let action_row = modal.components.first().context("No modal components??")?;
+            if let ModalInteractionComponent::ActionRow(ar) = action_row {
+                let text_component = ar
+                    .components
+                    .iter()
+                    .find(|c| {
+                        if let ModalInteractionComponent::TextInput(cc) = c {
+                            cc.custom_id == self.jump_modal_text_cid
+                        } else {
+                            false
+                        }
+                    })
+                    .map(|c| {
+                        if let ModalInteractionComponent::TextInput(cc) = c {
+                            cc
+                        } else {
+                            panic!("??");
+                        }
+                    })
+                    .context("No page jump component??")?;
+
+                let parsed = text_component
+                    .value
+                    .clone()
+                    .parse::<u64>()
+                    .context("Invalid page number")?;
+
+                if parsed > pages as u64 || parsed < 1 {
+                    bail!("That page doesn't exist.");
+                };
+
+                self.current_page = parsed;
+            } else {
+                bail!("Somehow, this wasn't an action row.")
+            }
+        }
+
+        if data.custom_id == self.page_next_cid {
+            self.current_page += 1;
+        } else if data.custom_id == self.page_prev_cid {
+            self.current_page -= 1;
+        };
+
+        if self.current_page > pages as u64 {
+            self.current_page = 1;
+        } else if self.current_page < 1 {
+            self.current_page = pages as u64;
+        }
+
+        let offset = (self.current_page as i64 - 1) * DEFAULT_LIST_COUNT;
+
+        let tags = match self.target_user_id {
+            Some(u) => match self.search_criteria {
+                Some(ref s) => {
+                    let all = Tag::search_in_guild_for_user(
+                        &data.assyst.database_handler,
+                        data.invocation_guild_id.unwrap().get() as i64,
+                        u.get() as i64,
+                        s,
+                    )
+                    .await?;
+                    all[offset as usize..(offset + DEFAULT_LIST_COUNT).clamp(1, self.tag_count as i64) as usize]
+                        .iter()
+                        .map(|r| (r.tag.clone(), Some(r.snippet.clone())))
+                        .collect::<Vec<_>>()
+                },
+                None => Tag::get_paged_for_user(
+                    &data.assyst.database_handler,
+                    data.invocation_guild_id.unwrap().get() as i64,
+                    u.get() as i64,
+                    offset,
+                    DEFAULT_LIST_COUNT,
+                )
+                .await?
+                .into_iter()
+                .map(|t| (t, None))
+                .collect(),
+            },
+            None => match self.search_criteria {
+                Some(ref s) => {
+                    let all = Tag::search_in_guild(
+                        &data.assyst.database_handler,
+                        data.invocation_guild_id.unwrap().get() as i64,
+                        s,
+                    )
+                    .await?;
+                    all[offset as usize..(offset + DEFAULT_LIST_COUNT).clamp(1, self.tag_count as i64) as usize]
+                        .iter()
+                        .map(|r| (r.tag.clone(), Some(r.snippet.clone())))
+                        .collect::<Vec<_>>()
+                },
+                None => Tag::get_paged(
+                    &data.assyst.database_handler,
+                    data.invocation_guild_id.unwrap().get() as i64,
+                    offset,
+                    DEFAULT_LIST_COUNT,
+                )
+                .await?
+                .into_iter()
+                .map(|t| (t, None))
+                .collect(),
+            },
+        };
+
+        let mut message = format!(
+            "🗒️ **Tags in this server{0}{1}**\nView a tag by running `{2}t <name>`\n\n",
+            {
+                match self.search_criteria {
+                    Some(ref s) => format!(" with search criteria {}", s.codestring()),
+                    None => String::new(),
+                }
+            },
+            {
+                match self.target_user_id {
+                    Some(u) => format!(" for user <@{u}>"),
+                    None => String::new(),
+                }
+            },
+            self.calling_prefix,
+        );
+
+        for (index, (tag, snippet)) in tags.iter().enumerate() {
+            let offset = (index as i64) + offset + 1;
+            writeln!(
+                message,
+                "{}. {} {}",
+                offset,
+                tag.name.to_ascii_lowercase(),
+                match self.target_user_id {
+                    Some(_) => String::new(),
+                    None => format!("(<@{}>)", tag.author),
+                }
+            )?;
+
+            if let Some(snippet) = snippet {
+                writeln!(message, "> {}", format_search_snippet(snippet))?;
+            }
+        }
+
+        write!(
+            message,
+            "\nShowing {} tags (page {}/{pages}) ({} total tags)",
+            tags.len(),
+            self.current_page,
+            self.tag_count
+        )?;
+
+        respond_update_text(
+            data.assyst.clone(),
+            data.interaction_id,
+            &data.interaction_token,
+            &message,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct TagListFlags {
+    pub page: u64,
+}
+impl FlagDecode for TagListFlags {
+    fn from_str(input: &str) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut valid_flags = HashMap::new();
+        valid_flags.insert("page", FlagType::WithValue);
+
+        let raw_decode = flags_from_str(input, valid_flags)?;
+        let page = raw_decode
+            .get("page")
+            .and_then(|x| x.as_deref())
+            .map_or(Ok(1), str::parse)
+            .context("Failed to parse page number")?;
+
+        let result = Self { page };
+
+        Ok(result)
+    }
+}
+impl ParseArgument for TagListFlags {
+    fn as_command_options(_: &str) -> Vec<twilight_model::application::command::CommandOption> {
+        vec![IntegerBuilder::new("page", "go to this page").required(false).build()]
+    }
+
+    async fn parse_raw_message(
+        ctxt: &mut crate::command::RawMessageParseCtxt<'_>,
+        label: crate::command::Label,
+    ) -> Result<Self, crate::command::errors::TagParseError> {
+        let args = ctxt.rest_all(label);
+        let parsed = Self::from_str(&args).map_err(TagParseError::FlagParseError)?;
+        Ok(parsed)
+    }
+
+    async fn parse_command_option(
+        ctxt: &mut crate::command::InteractionCommandParseCtxt<'_>,
+        _: crate::command::Label,
+    ) -> Result<Self, TagParseError> {
+        let page = int_arg_u64!(ctxt, "page", 1);
+
+        Ok(Self { page })
+    }
+}
+
+#[command(
+    description = "list tags in the server (or owned by a certain user in the server)",
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "<user id|mention>",
+    examples = ["@jacher"],
+    flag_descriptions = [("page <page>", "start at this page number")],
+    guild_only = true,
+    context_menu_user_command = "List Owned Tags",
+    group_parent_name = "tag"
+)]
+pub async fn list(ctxt: CommandCtxt<'_>, user: Option<User>, flags: TagListFlags) -> anyhow::Result<()> {
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tags can only be listed in guilds.")
+    };
+
+    let page = flags.page.clamp(1, u64::MAX);
+
+    // user-specific search if arg is a mention
+    let user_id = user.map(|x| x.0.id);
+
+    ensure!(page >= 1, "Page must be greater or equal to 1");
+
+    let offset = (page as i64 - 1) * DEFAULT_LIST_COUNT;
+    let count = match user_id {
+        Some(u) => Tag::get_count_for_user(&ctxt.assyst().database_handler, guild_id.get() as i64, u.get() as i64)
+            .await
+            .context("Failed to get tag count for user in guild")?,
+        None => Tag::get_count_in_guild(&ctxt.assyst().database_handler, guild_id.get() as i64)
+            .await
+            .context("Failed to get tag count in guild")?,
+    };
+
+    ensure!(count > 0, "No tags found for the requested filter");
+    let pages = (count as f64 / DEFAULT_LIST_COUNT as f64).ceil() as i64;
+    ensure!(pages >= page as i64, "Cannot go beyond final page");
+
+    let tags = match user_id {
+        Some(u) => {
+            Tag::get_paged_for_user(
+                &ctxt.assyst().database_handler,
+                guild_id.get() as i64,
+                u.get() as i64,
+                offset,
+                DEFAULT_LIST_COUNT,
+            )
+            .await?
+        },
+        None => {
+            Tag::get_paged(
+                &ctxt.assyst().database_handler,
+                guild_id.get() as i64,
+                offset,
+                DEFAULT_LIST_COUNT,
+            )
+            .await?
+        },
+    };
+
+    let mut message = format!(
+        "🗒️ **Tags in this server{0}**\nView a tag by running `{1}t <name>`\n\n",
+        {
+            match user_id {
+                Some(u) => format!(" for user <@{u}>"),
+                None => String::new(),
+            }
+        },
+        ctxt.data.calling_prefix,
+    );
+
+    for (index, tag) in tags.iter().enumerate() {
+        let offset = (index as i64) + offset + 1;
+        writeln!(
+            message,
+            "{}. {} {}",
+            offset,
+            tag.name.to_ascii_lowercase(),
+            match user_id {
+                Some(_) => String::new(),
+                None => format!("(<@{}>)", tag.author),
+            }
+        )?;
+    }
+
+    write!(
+        message,
+        "\nShowing {} tags (page {page}/{pages}) ({count} total tags)",
+        tags.len()
+    )?;
+
+    let timestamp = unix_timestamp();
+    let page_next = format!("page_next-{timestamp}");
+    let page_prev = format!("page_prev-{timestamp}");
+    let jump_to_page = format!("page_jump-{timestamp}");
+    let modal_cid = format!("page_jump-modal-{timestamp}");
+    let modal_text_cid = format!("page_jump-modal-text-{timestamp}");
+
+    ctxt.reply(MessageBuilder {
+        content: Some(message),
+        attachment: None,
+        components: Some(vec![
+            Component::Button(button_emoji_new(
+                &page_prev,
+                EmojiReactionType::Custom {
+                    name: Some("arrow_left".to_owned()),
+                    animated: false,
+                    id: Id::<EmojiMarker>::new(1272681864204779560),
+                },
+                ButtonStyle::Secondary,
+            )),
+            Component::Button(button_new(&jump_to_page, "Jump", ButtonStyle::Primary)),
+            Component::Button(button_emoji_new(
+                &page_next,
+                EmojiReactionType::Custom {
+                    name: Some("arrow_right".to_owned()),
+                    animated: false,
+                    id: Id::<EmojiMarker>::new(1272681890129645568),
+                },
+                ButtonStyle::Secondary,
+            )),
+        ]),
+        component_ctxt: Some((
+            vec![
+                page_next.clone(),
+                page_prev.clone(),
+                jump_to_page.clone(),
+                modal_cid.clone(),
+            ],
+            ComponentCtxt::new(
+                ctxt.assyst().clone(),
+                ComponentMetadata::TagList(TagPaginatorComponentMetadata {
+                    page_next_cid: page_next,
+                    page_prev_cid: page_prev,
+                    page_jump_cid: jump_to_page,
+                    jump_modal_cid: modal_cid,
+                    jump_modal_text_cid: modal_text_cid,
+                    current_page: page,
+                    invocating_user_id: ctxt.data.author.id,
+                    target_user_id: user_id,
+                    tag_count: count as u64,
+                    calling_prefix: ctxt.data.calling_prefix.clone(),
+                    search_criteria: None,
+                }),
+            ),
+        )),
+    })
+    .await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "get information about a tag in the server",
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[name]",
+    examples = ["test", "script"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn info(
+    ctxt: CommandCtxt<'_>,
+    #[autocomplete = "crate::command::misc::tag::tag_names_autocomplete"] name: WordAutocomplete,
+) -> anyhow::Result<()> {
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tag information can only be fetched in guilds.")
+    };
+
+    let tag = Tag::get(
+        &ctxt.assyst().database_handler,
+        guild_id.get() as i64,
+        &name.0.to_ascii_lowercase(),
+    )
+    .await?
+    .context("Tag not found in this server.")?;
+
+    let collaborators = TagCollaborator::get_for_tag(&ctxt.assyst().database_handler, tag.guild_id, &tag.name)
+        .await
+        .context("Failed to fetch tag collaborators")?;
+
+    let fmt = format_discord_timestamp(tag.created_at as u64);
+    let mut message = format!(
+        "🗒️ **Tag information: **{}\n\nAuthor: <@{}>\nCreated: {}",
+        tag.name.to_ascii_lowercase(),
+        tag.author,
+        fmt
+    );
+
+    if !collaborators.is_empty() {
+        write!(
+            message,
+            "\nCollaborators: {}",
+            collaborators
+                .iter()
+                .map(|c| format!("<@{}>", c.user_id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+    }
+
+    let usage = TagUsageStats::get(&ctxt.assyst().database_handler, tag.guild_id, &tag.name)
+        .await
+        .context("Failed to fetch tag usage statistics")?;
+
+    if TagLock::get(&ctxt.assyst().database_handler, tag.guild_id, &tag.name)
+        .await
+        .context("Failed to fetch tag lock")?
+        .is_some()
+    {
+        message.push_str("\nLocked: yes");
+    }
+
+    if let Some(usage) = usage {
+        write!(
+            message,
+            "\nUses: {}\nUnique users: {}\nLast used: {}",
+            usage.uses,
+            usage.unique_users,
+            format_discord_timestamp(usage.last_used as u64)
+        )?;
+    } else {
+        message.push_str("\nUses: 0");
+    }
+
+    ctxt.reply(message).await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "get the raw content of a tag without parsing it",
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[name]",
+    examples = ["test", "script"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn raw(
+    ctxt: CommandCtxt<'_>,
+    #[autocomplete = "crate::command::misc::tag::tag_names_autocomplete"] name: WordAutocomplete,
+) -> anyhow::Result<()> {
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tag raw content can only be fetched in guilds.")
+    };
+
+    let tag = Tag::get(
+        &ctxt.assyst().database_handler,
+        guild_id.get() as i64,
+        &name.0.to_ascii_lowercase(),
+    )
+    .await?
+    .context("Tag not found in this server.")?;
+
+    ctxt.reply(Attachment {
+        name: format!("tag-{}.txt", name.0).into_boxed_str(),
+        data: tag.data.into_bytes(),
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Flattens a search snippet onto a single line so it can be quoted under its tag.
+fn format_search_snippet(snippet: &str) -> String {
+    let snippet = snippet
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('`', "'");
+
+    if snippet.chars().count() > MAX_SEARCH_SNIPPET_LENGTH {
+        format!(
+            "{}...",
+            snippet.chars().take(MAX_SEARCH_SNIPPET_LENGTH).collect::<String>()
+        )
+    } else {
+        snippet
+    }
+}
+
+#[command(
+    description = "search for tags in a server by name and contents",
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[query] <page> <user id|mention>",
+    examples = ["1 test @jacher", "1 test"],
+    guild_only = true
+)]
+pub async fn search(ctxt: CommandCtxt<'_>, query: Word, user: Option<User>) -> anyhow::Result<()> {
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tags can only be listed in guilds.")
+    };
+
+    // user-specific search if arg is a mention
+    let user_id = user.map(|x| x.0.id);
+
+    let page = 1;
+
+    ensure!(page >= 1, "Page must be greater or equal to 1");
+
+    let offset = (page as i64 - 1) * DEFAULT_LIST_COUNT;
+    let tags = match user_id {
+        Some(u) => {
+            Tag::search_in_guild_for_user(
+                &ctxt.assyst().database_handler,
+                guild_id.get() as i64,
+                u.get() as i64,
+                &query.0.to_ascii_lowercase(),
+            )
+            .await?
+        },
+        None => {
+            Tag::search_in_guild(
+                &ctxt.assyst().database_handler,
+                guild_id.get() as i64,
+                &query.0.to_ascii_lowercase(),
+            )
+            .await?
+        },
+    };
+    let count = tags.len();
+
+    ensure!(count > 0, "No tags found for the requested filter");
+    let pages = (count as f64 / DEFAULT_LIST_COUNT as f64).ceil() as i64;
+    ensure!(pages >= page as i64, "Cannot go beyond final page");
+
+    let tags = &tags[offset as usize..(offset + DEFAULT_LIST_COUNT).clamp(1, tags.len() as i64) as usize];
+
+    let mut message = format!(
+        "🗒️ **Tags in this server with search criteria {0}{1}**\nView a tag by running `{2}t <name>`, or go to the next page by running `{2}t list {3} {0}`\n\n",
+        query.0,
+        {
+            match user_id {
+                Some(u) => format!(" for user <@{u}>"),
+                None => String::new(),
+            }
+        },
+        ctxt.data.calling_prefix,
+        page + 1
+    );
+
+    for (index, TagSearchResult { tag, snippet }) in tags.iter().enumerate() {
+        let offset = (index as i64) + offset + 1;
+        writeln!(
+            message,
+            "{}. {} {}",
+            offset,
+            tag.name.to_ascii_lowercase(),
+            match user_id {
+                Some(_) => String::new(),
+                None => format!("(<@{}>)", tag.author),
+            }
+        )?;
+        writeln!(message, "> {}", format_search_snippet(snippet))?;
+    }
+
+    write!(
+        message,
+        "\nShowing {} tags (page {page}/{pages}) ({count} total tags)",
+        tags.len()
+    )?;
+
+    let timestamp = unix_timestamp();
+    let page_next = format!("page_next-{timestamp}");
+    let page_prev = format!("page_prev-{timestamp}");
+    let jump_to_page = format!("page_jump-{timestamp}");
+    let modal_cid = format!("page_jump-modal-{timestamp}");
+    let modal_text_cid = format!("page_jump-modal-text-{timestamp}");
+
+    ctxt.reply(MessageBuilder {
+        content: Some(message),
+        attachment: None,
+        components: Some(vec![
+            Component::Button(button_emoji_new(
+                &page_prev,
+                EmojiReactionType::Custom {
+                    name: Some("arrow_left".to_owned()),
+                    animated: false,
+                    id: Id::<EmojiMarker>::new(1272681864204779560),
+                },
+                ButtonStyle::Secondary,
+            )),
+            Component::Button(button_new(&jump_to_page, "Jump", ButtonStyle::Primary)),
+            Component::Button(button_emoji_new(
+                &page_next,
+                EmojiReactionType::Custom {
+                    name: Some("arrow_right".to_owned()),
+                    animated: false,
+                    id: Id::<EmojiMarker>::new(1272681890129645568),
+                },
+                ButtonStyle::Secondary,
+            )),
+        ]),
+        component_ctxt: Some((
+            vec![
+                page_next.clone(),
+                page_prev.clone(),
+                jump_to_page.clone(),
+                modal_cid.clone(),
+            ],
+            ComponentCtxt::new(
+                ctxt.assyst().clone(),
+                ComponentMetadata::TagList(TagPaginatorComponentMetadata {
+                    page_next_cid: page_next,
+                    page_prev_cid: page_prev,
+                    page_jump_cid: jump_to_page,
+                    jump_modal_cid: modal_cid,
+                    jump_modal_text_cid: modal_text_cid,
+                    current_page: page,
+                    invocating_user_id: ctxt.data.author.id,
+                    target_user_id: user_id,
+                    tag_count: count as u64,
+                    calling_prefix: ctxt.data.calling_prefix.clone(),
+                    search_criteria: Some(query.0),
+                }),
+            ),
+        )),
+    })
+    .await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "retrieve a dump of all your owned tags in the current server",
+    cooldown = Duration::from_secs(5),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "",
+    examples = [""],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn backup(ctxt: CommandCtxt<'_>) -> anyhow::Result<()> {
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tags can only be backed up from guilds.")
+    };
+
+    let all_author = Tag::get_for_user(
+        &ctxt.assyst().database_handler,
+        guild_id.get() as i64,
+        ctxt.data.author.id.get() as i64,
+    )
+    .await
+    .context("Failed to fetch tags")?;
+
+    ensure!(!all_author.is_empty(), "You don't own any tags in this server.");
+
+    let mut buf = Cursor::new(Vec::new());
+    let mut zip = ZipWriter::new(&mut buf);
+
+    fn sanitise_filename(n: &str) -> String {
+        let mut sanitise_round1 = n.replace(['/', std::ptr::null::<char>() as u8 as char], "?");
+        if sanitise_round1 == ".." {
+            sanitise_round1 = "__..??".to_owned();
+        } else if sanitise_round1 == "." {
+            sanitise_round1 = "__.??".to_owned();
+        }
+
+        sanitise_round1
+    }
+
+    for (i, tag) in all_author.iter().enumerate() {
+        let name = format!("tag-{i}-{}.txt", sanitise_filename(&tag.name));
+        zip.start_file(name, SimpleFileOptions::default())?;
+        zip.write_all(tag.data.as_bytes())?;
+    }
+
+    // the file names above are lossy, so keep an exact copy for `tag restore`
+    let export = all_author
+        .iter()
+        .map(|tag| TagBackupEntry {
+            name: tag.name.clone(),
+            data: tag.data.clone(),
+            created_at: Some(tag.created_at),
+        })
+        .collect::<Vec<_>>();
+    zip.start_file("tags.json", SimpleFileOptions::default())?;
+    zip.write_all(&serde_json::to_vec(&export)?)?;
+
+    let finished = zip.finish()?;
+    let out = finished.clone().into_inner();
+
+    ctxt.reply(Attachment {
+        name: "tags.zip".into(),
+        data: out,
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// A single tag in the JSON export written alongside the text files by `tag backup`.
+#[derive(Serialize, Deserialize)]
+struct TagBackupEntry {
+    name: String,
+    data: String,
+    /// Only informational, as restored tags are always created at the time of the restore
+    #[serde(default)]
+    created_at: Option<i64>,
+}
+
+#[command(
+    description = "copy a tag to your clipboard (use tag paste to paste a copied tag)",
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[name]",
+    examples = ["test", "script"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn copy(
+    ctxt: CommandCtxt<'_>,
+    #[autocomplete = "crate::command::misc::tag::tag_names_autocomplete"] name: WordAutocomplete,
+) -> anyhow::Result<()> {
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tags can only be copied from guilds.")
+    };
+
+    let tag = Tag::get(
+        &ctxt.assyst().database_handler,
+        guild_id.get() as i64,
+        &name.0.to_ascii_lowercase(),
+    )
+    .await?
+    .context("Tag not found in this server.")?;
+
+    ctxt.assyst()
+        .database_handler
+        .cache
+        .insert_copied_tag(ctxt.data.author.id.get(), tag.data);
+
+    ctxt.reply(format!("Tag {} copied successfully.", name.0)).await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "paste the tag on your clipboard",
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[name]",
+    examples = ["test2"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn paste(ctxt: CommandCtxt<'_>, name: Word) -> anyhow::Result<()> {
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tags can only be pasted into guilds.")
+    };
+
+    ensure!(name.0.len() < 20, "Tag names cannot exceed 20 characters.");
+    ensure!(
+        !RESERVED_NAMES.contains(&&name.0[..]),
+        "Tag names cannot be a reserved word."
+    );
+    ensure!(!name.0.contains(' '), "Tag names cannot contain spaces.");
+    ensure!(
+        TagAlias::get(&ctxt.assyst().database_handler, guild_id.get() as i64, &name.0)
+            .await?
+            .is_none(),
+        "That tag name is already used as an alias in this server."
+    );
+
+    let content = ctxt
+        .assyst()
+        .database_handler
+        .cache
+        .get_copied_tag(ctxt.data.author.id.get())
+        .context("You don't have any tag copied. It may have expired.")?;
+
+    let t = Tag {
+        guild_id: guild_id.get() as i64,
+        name: name.0.clone(),
+        data: content,
+        author: ctxt.data.author.id.get() as i64,
+        created_at: unix_timestamp() as i64,
+    };
+
+    let success = t
+        .set(&ctxt.assyst().database_handler)
+        .await
+        .context("Failed to create tag")?;
+
+    ensure!(success, "That tag name is already used in this server.");
+
+    ctxt.reply(format!("Tag {} pasted successfully.", name.0)).await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "transfer ownership of a tag that you own (server managers can transfer any tag)",
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[name] [user id|mention]",
+    examples = ["test @jacher", "script 233667448887312385"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn transfer(
+    ctxt: CommandCtxt<'_>,
+    #[autocomplete = "crate::command::misc::tag::tag_names_autocomplete_for_user"] name: WordAutocomplete,
+    user: User,
+) -> anyhow::Result<()> {
+    let author = ctxt.data.author.id.get();
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tags can only be transferred in guilds.")
+    };
+
+    ensure!(!user.0.bot, "Tags cannot be transferred to bots.");
+
+    let tag = Tag::get(
+        &ctxt.assyst().database_handler,
+        guild_id.get() as i64,
+        &name.0.to_ascii_lowercase(),
+    )
+    .await?
+    .context("Tag not found in this server.")?;
+
+    let is_manager = ctxt
+        .assyst()
+        .rest_cache_handler
+        .user_is_guild_manager(guild_id.get(), author)
+        .await
+        .context("Failed to fetch user permissions")?;
+
+    ensure!(
+        is_manager || tag.author == author as i64,
+        "Failed to transfer that tag. Do you own it?"
+    );
+    if !is_manager {
+        ensure_not_locked(ctxt.assyst(), tag.guild_id, &tag.name).await?;
+    }
+    ensure!(tag.author != user.0.id.get() as i64, "That user already owns this tag.");
+
+    Tag::transfer(
+        &ctxt.assyst().database_handler,
+        tag.guild_id,
+        &tag.name,
+        user.0.id.get() as i64,
+    )
+    .await
+    .context("Failed to transfer tag")?;
+
+    // the new owner no longer needs to be a collaborator
+    TagCollaborator {
+        guild_id: tag.guild_id,
+        name: tag.name.clone(),
+        user_id: user.0.id.get() as i64,
+    }
+    .delete(&ctxt.assyst().database_handler)
+    .await
+    .context("Failed to update tag collaborators")?;
+
+    ctxt.reply(format!(
+        "Successfully transferred tag {} to <@{}>",
+        tag.name.codestring(),
+        user.0.id
+    ))
+    .await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "add, remove or list users who can edit a tag that you own",
+    aliases = ["collaborator", "collab"],
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[add|remove|list] [name] <user id|mention>",
+    examples = ["add test @jacher", "remove test @jacher", "list test"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn collaborators(
+    ctxt: CommandCtxt<'_>,
+    action: Word,
+    #[autocomplete = "crate::command::misc::tag::tag_names_autocomplete_for_user"] name: WordAutocomplete,
+    user: Option<User>,
+) -> anyhow::Result<()> {
+    let author = ctxt.data.author.id.get();
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tag collaborators can only be managed in guilds.")
+    };
+
+    let tag = Tag::get(
+        &ctxt.assyst().database_handler,
+        guild_id.get() as i64,
+        &name.0.to_ascii_lowercase(),
+    )
+    .await?
+    .context("Tag not found in this server.")?;
+
+    let action = action.0.to_ascii_lowercase();
+
+    if action == "list" {
+        let collaborators = TagCollaborator::get_for_tag(&ctxt.assyst().database_handler, tag.guild_id, &tag.name)
+            .await
+            .context("Failed to fetch tag collaborators")?;
+
+        ensure!(!collaborators.is_empty(), "This tag has no collaborators.");
+
+        let mut message = format!("🗒️ **Collaborators for tag {}**\n\n", tag.name.codestring());
+        for (index, collaborator) in collaborators.iter().enumerate() {
+            writeln!(message, "{}. <@{}>", index + 1, collaborator.user_id)?;
+        }
+
+        ctxt.reply(message).await?;
+
+        return Ok(());
+    }
+
+    let user = user.context("Please provide a user to add or remove as a collaborator.")?;
+
+    ensure!(
+        tag.author == author as i64,
+        "Only the owner of a tag can manage its collaborators."
+    );
+    ensure_not_locked(ctxt.assyst(), tag.guild_id, &tag.name).await?;
+
+    let collaborator = TagCollaborator {
+        guild_id: tag.guild_id,
+        name: tag.name.clone(),
+        user_id: user.0.id.get() as i64,
+    };
+
+    match &action[..] {
+        "add" => {
+            ensure!(!user.0.bot, "Bots cannot be tag collaborators.");
+            ensure!(
+                collaborator.user_id != tag.author,
+                "You can't add yourself as a collaborator."
+            );
+
+            let success = collaborator
+                .set(&ctxt.assyst().database_handler)
+                .await
+                .context("Failed to add collaborator")?;
+
+            ensure!(success, "That user is already a collaborator on this tag.");
+
+            ctxt.reply(format!("<@{}> can now edit tag {}", user.0.id, tag.name.codestring()))
+                .await?;
+        },
+        "remove" => {
+            let success = collaborator
+                .delete(&ctxt.assyst().database_handler)
+                .await
+                .context("Failed to remove collaborator")?;
+
+            ensure!(success, "That user is not a collaborator on this tag.");
+
+            ctxt.reply(format!(
+                "<@{}> can no longer edit tag {}",
+                user.0.id,
+                tag.name.codestring()
+            ))
+            .await?;
+        },
+        _ => bail!(
+            "Unknown action {}. Valid actions are add, remove and list.",
+            action.codestring()
+        ),
+    }
+
+    Ok(())
+}
+
+#[command(
+    description = "create an alternative name for a tag",
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[alias] [target tag name]",
+    examples = ["t test", "js script"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn alias(
+    ctxt: CommandCtxt<'_>,
+    alias: Word,
+    #[autocomplete = "crate::command::misc::tag::tag_names_autocomplete"] target: WordAutocomplete,
+) -> anyhow::Result<()> {
+    let author = ctxt.data.author.id.get();
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tag aliases can only be created in guilds.")
+    };
+
+    let alias = alias.0.to_ascii_lowercase();
+
+    ensure!(alias.len() < 20, "Tag names cannot exceed 20 characters.");
+    ensure!(
+        !RESERVED_NAMES.contains(&&alias[..]),
+        "Tag names cannot be a reserved word."
+    );
+    ensure!(!alias.contains(' '), "Tag names cannot contain spaces.");
+
+    // resolves the target if it is an alias itself, so aliases never chain
+    let tag = Tag::get(
+        &ctxt.assyst().database_handler,
+        guild_id.get() as i64,
+        &target.0.to_ascii_lowercase(),
+    )
+    .await?
+    .context("Tag not found in this server.")?;
+
+    ensure!(
+        Tag::get(&ctxt.assyst().database_handler, guild_id.get() as i64, &alias)
+            .await?
+            .is_none(),
+        "That tag name is already used in this server."
+    );
+
+    let success = TagAlias {
+        guild_id: tag.guild_id,
+        alias: alias.clone(),
+        target: tag.name.clone(),
+        author: author as i64,
+        created_at: unix_timestamp() as i64,
+    }
+    .set(&ctxt.assyst().database_handler)
+    .await
+    .context("Failed to create alias")?;
+
+    ensure!(success, "That alias is already used in this server.");
+
+    ctxt.reply(format!(
+        "Successfully created alias {} for tag {}",
+        alias.codestring(),
+        tag.name.codestring()
+    ))
+    .await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "get the most-used tags in the server",
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "",
+    examples = [""],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn top(ctxt: CommandCtxt<'_>) -> anyhow::Result<()> {
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tag statistics can only be fetched in guilds.")
+    };
+
+    let top_tags =
+        TagUsageStats::get_top_in_guild(&ctxt.assyst().database_handler, guild_id.get() as i64, TOP_TAGS_COUNT)
+            .await
+            .context("Failed to get tag usage statistics")?;
+
+    ensure!(!top_tags.is_empty(), "No tags have been used in this server yet.");
+
+    let top_tags_formatted_raw = top_tags
+        .iter()
+        .map(|t| {
+            (
+                &t.name[..],
+                format!("{} {}", t.uses, format!("({} users)", t.unique_users).fg_green()),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let top_tags_formatted = top_tags_formatted_raw
+        .iter()
+        .map(|(a, b)| (a.fg_yellow(), &b[..]))
+        .collect::<Vec<_>>();
+
+    let table = generate_list_fixed_delim(&"Tag".fg_cyan(), &"Uses".fg_cyan(), &top_tags_formatted, 3, 4);
+
+    ctxt.reply(table.codeblock("ansi")).await?;
+
+    Ok(())
+}
+
+// autocomplete still runs in DMs for guild-only subcommands, as `/tag` is available everywhere
+pub async fn tag_names_autocomplete(assyst: ThreadSafeAssyst, data: AutocompleteData) -> Vec<String> {
+    let Some(guild_id) = data.guild_id else {
+        return vec![];
+    };
+
+    Tag::get_names_in_guild(&assyst.database_handler, guild_id.get() as i64)
+        .await
+        .unwrap_or(vec![])
+        .iter()
+        .map(|x| x.1.clone())
+        .collect::<Vec<_>>()
+}
+
+pub async fn tag_names_autocomplete_for_user(assyst: ThreadSafeAssyst, data: AutocompleteData) -> Vec<String> {
+    let Some(guild_id) = data.guild_id else {
+        return vec![];
+    };
+
+    Tag::get_names_in_guild(&assyst.database_handler, guild_id.get() as i64)
+        .await
+        .unwrap_or(vec![])
+        .iter()
+        .filter_map(|x| {
+            if x.0 == data.user.id.get() {
+                Some(x.1.clone())
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>()
+}
+
+/// Fails if a server manager has locked the tag, which stops it from being edited.
+async fn ensure_not_locked(assyst: &ThreadSafeAssyst, guild_id: i64, name: &str) -> anyhow::Result<()> {
+    let lock = TagLock::get(&assyst.database_handler, guild_id, name)
+        .await
+        .context("Failed to fetch tag lock")?;
+
+/// Suggests tags in the current server, followed by the user's personal tags.
+pub async fn run_tag_names_autocomplete(assyst: ThreadSafeAssyst, data: AutocompleteData) -> Vec<String> {
+    let mut names = match data.guild_id {
+        Some(guild_id) => Tag::get_names_in_guild(&assyst.database_handler, guild_id.get() as i64)
+            .await
+            .unwrap_or(vec![])
+            .into_iter()
+            .map(|x| x.1)
+            .collect::<Vec<_>>(),
+        None => vec![],
+    };
+
+    let personal = UserTag::get_for_user(&assyst.database_handler, data.user.id.get() as i64)
+        .await
+        .unwrap_or(vec![]);
+
+    for tag in personal {
+        if !names.contains(&tag.name) {
+            names.push(tag.name);
+        }
+    }
+
+    names
+}
+
+#[command(
+    description = "run a tag in the current server, or one of your personal tags",
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[tag name] <arguments...>",
+    examples = ["test", "whatever"],
+    send_processing = true,
+    group_parent_name = "tag"
+)]
+pub async fn default(
+    ctxt: CommandCtxt<'_>,
+    #[autocomplete = "crate::command::misc::tag::run_tag_names_autocomplete"] tag_name: WordAutocomplete,
+    arguments: Option<Vec<Word>>,
+) -> anyhow::Result<()> {
+    let name = tag_name.0.to_ascii_lowercase();
+    let arguments = arguments
+        .unwrap_or_default()
+        .into_iter()
+        .map(|Word(word)| word)
+        .collect::<Vec<_>>();
+
+    // tags in the server take precedence over personal tags with the same name
+    let guild_tag = match ctxt.data.guild_id {
+        Some(guild_id) => Tag::get(&ctxt.assyst().database_handler, guild_id.get() as i64, &name)
+            .await
+            .context("Failed to fetch tag")?,
+        None => None,
+    };
+
+    if let Some(tag) = guild_tag {
+        record_tag_use(
+            ctxt.assyst().clone(),
+            tag.guild_id,
+            tag.name.clone(),
+            ctxt.data.author.id.get() as i64,
+        );
+
+        return run_tag_and_reply(&ctxt, tag.name, tag.data, tag.author as u64, None, arguments).await;
+    }
+
+    let tag = UserTag::get(&ctxt.assyst().database_handler, ctxt.data.author.id.get() as i64, &name)
+        .await
+        .context("Failed to fetch personal tag")?
+        .context(if ctxt.data.guild_id.is_some() {
+            "Tag not found in this server."
+        } else {
+            "You don't have a personal tag with that name."
+        })?;
+
+    run_tag_and_reply(
+        &ctxt,
+        tag.name,
+        tag.data,
+        tag.user_id as u64,
+        Some(tag.user_id as u64),
+        arguments,
+    )
+    .await
+}
+
+/// Runs a tag and replies with its output and components.\
+/// `owner` is the owner of the tag, and `personal_owner` is also set to them if it is a personal tag.
+async fn run_tag_and_reply(
+    ctxt: &CommandCtxt<'_>,
+    name: String,
+    data: String,
+    owner: u64,
+    personal_owner: Option<u64>,
+    arguments: Vec<String>,
+) -> anyhow::Result<()> {
+    let tcx = TagContext {
+        tokio: Handle::current(),
+        message: ctxt.data.message.cloned(),
+        assyst: ctxt.assyst().clone(),
+        guild_id: ctxt.data.guild_id.map(Id::get),
+        channel_id: ctxt.data.channel_id.get(),
+        author: ctxt.data.author.clone(),
+        owner,
+    };
+
+    let budget_key = (
+        personal_owner
+            .or(ctxt.data.guild_id.map(Id::get))
+            .unwrap_or(ctxt.data.author.id.get()),
+        name.clone(),
+    );
+    let (res, data) = run_tag(tcx, data, arguments, budget_key).await;
+
+    match res {
+        Ok(ParseResult {
+            output,
+            attachment,
+            components,
+            diagnostics,
+            ..
+        }) => {
+            let (components, component_ctxt) = tag_components(
+                ctxt.assyst().clone(),
+                &components,
+                name,
+                owner,
+                personal_owner,
+                ctxt.data.guild_id.map(Id::get),
+                ctxt.data.channel_id.get(),
+            )
+            .unzip();
+
+            ctxt.reply(MessageBuilder {
+                content: Some(output_with_diagnostics(output, &diagnostics)),
+                attachment: attachment.map(|(buf, _)| Image(buf).into()),
+                components,
+                component_ctxt,
+            })
+            .await?;
+        },
+        Err(err) => {
+            ctxt.reply(assyst_tag::errors::format_error(&data, err).codeblock("ansi"))
+                .await?;
+        },
+    }
+
+    Ok(())
+}
+
+/// Executes a tag on a blocking thread, returning the result along with the tag source.\
+/// Errors that can be recovered from don't stop the tag, and are returned as diagnostics instead.
+///
+/// `budget_key` identifies the tag for its execution budget, as (guild ID or personal tag owner ID,
+/// tag name). The tag is not ran at all if either the user's or the tag's budget is used up.
+/// Otherwise whatever is left of them is reserved up front, and the tag is stopped once it exceeds
+/// it.
+async fn run_tag(
+    tcx: TagContext,
+    data: String,
+    arguments: Vec<String>,
+    budget_key: (u64, String),
+) -> (TResult<ParseResult>, String) {
+    let assyst = tcx.assyst.clone();
+    let user_id = tcx.author.id.get();
+
+    let reserved = match assyst
+        .tag_execution_budgets
+        .reserve(user_id, &budget_key, limits::MAX_EXECUTION_TIME)
+    {
+        Ok(reserved) => reserved,
+        Err((scope, retry_after)) => {
+            return (
+                Err(assyst_tag::errors::err(ErrorKind::ExecutionBudget {
+                    scope,
+                    retry_after,
+                })),
+                data,
+            );
+        },
+    };
+
+    let start = Instant::now();
+    let deadline = start + reserved;
+
+    let result = tokio::task::spawn_blocking(move || {
+        let arguments: Vec<&str> = arguments.iter().map(|a| &**a).collect();
+
+        (
+            assyst_tag::parse_with_deadline(&data, &arguments, ParseMode::CollectDiagnostics, tcx, deadline),
+            data,
+        )
+    })
+    .await
+    .expect("Tag task panicked");
+
+    assyst
+        .tag_execution_budgets
+        .refund(user_id, &budget_key, reserved.saturating_sub(start.elapsed()));
+
+    result
+}
+
+/// Adds the diagnostics of a tag under its output as warnings. The output is shortened if needed,
+/// so that the warnings are never cut off by the message length limit.
+fn output_with_diagnostics(output: String, diagnostics: &[Diagnostic]) -> String {
+    if diagnostics.is_empty() {
+        return output;
+    }
+
+    let mut warnings = String::new();
+    for diagnostic in diagnostics.iter().take(MAX_SHOWN_DIAGNOSTICS) {
+        let message = diagnostic.message.lines().next().unwrap_or_default();
+        let _ = write!(warnings, "\n-# ⚠️ {}", message.chars().take(200).collect::<String>());
+    }
+    if diagnostics.len() > MAX_SHOWN_DIAGNOSTICS {
+        let _ = write!(
+            warnings,
+            "\n-# ...and {} more warnings",
+            diagnostics.len() - MAX_SHOWN_DIAGNOSTICS
+        );
+    }
+
+    let max_output_length = 2000usize.saturating_sub(warnings.chars().count());
+    let mut output = output.chars().take(max_output_length).collect::<String>();
+    output.push_str(&warnings);
+    output
+}
+
+/// Records a use of a tag in the background, so that the database round trip does not hold up the tag itself.
+fn record_tag_use(assyst: ThreadSafeAssyst, guild_id: i64, name: String, user_id: i64) {
+    tokio::spawn(async move {
+        if let Err(e) = TagUsage::record(
+            &assyst.database_handler,
+            guild_id,
+            &name,
+            user_id,
+            unix_timestamp() as i64,
+        )
+        .await
+        {
+            err!("Failed to record use of tag {name} in guild {guild_id}: {e:?}");
+        }
+    });
+}
+
+/// Builds the action rows for the components declared by a tag, along with the context that
+/// re-runs the tag when any of them are used.
+fn tag_components(
+    assyst: ThreadSafeAssyst,
+    components: &[TagComponent],
+    tag_name: String,
+    owner: u64,
+    personal_owner: Option<u64>,
+    guild_id: Option<u64>,
+    channel_id: u64,
+) -> Option<(Vec<Component>, ComponentCtxtRegister)> {
+    if components.is_empty() {
+        return None;
+    }
+
+    let timestamp = unix_timestamp();
+    let mut rows = Vec::new();
+    let mut buttons = Vec::new();
+    let mut button_payloads = HashMap::new();
+    let mut select_options = Vec::new();
+    let mut select_payloads = Vec::new();
+
+    for component in components {
+        match component {
+            TagComponent::Button { label, payload } => {
+                let cid = format!("tag_button-{}-{timestamp}", buttons.len());
+                buttons.push(Component::Button(button_new(&cid, label, ButtonStyle::Secondary)));
+                button_payloads.insert(cid, payload.clone());
+            },
+            TagComponent::SelectOption { label, payload } => {
+                select_options.push((label.clone(), select_payloads.len().to_string()));
+                select_payloads.push(payload.clone());
+            },
+        }
+    }
+
+    if !buttons.is_empty() {
+        rows.push(Component::ActionRow(ActionRow {
+            id: None,
+            components: buttons,
+        }));
+    }
+
+    let select_cid = format!("tag_select-{timestamp}");
+    if !select_options.is_empty() {
+        rows.push(Component::ActionRow(ActionRow {
+            id: None,
+            components: vec![Component::SelectMenu(select_menu_new(&select_cid, select_options))],
+        }));
+    }
+
+    let mut cids = button_payloads.keys().cloned().collect::<Vec<_>>();
+    if !select_payloads.is_empty() {
+        cids.push(select_cid.clone());
+    }
+
+    Some((
+        rows,
+        (
+            cids,
+            ComponentCtxt::new(
+                assyst,
+                ComponentMetadata::TagComponents(TagComponentsMetadata {
+                    tag_name,
+                    owner,
+                    personal_owner,
+                    guild_id,
+                    channel_id,
+                    button_payloads,
+                    select_cid,
+                    select_payloads,
+                }),
+            ),
+        ),
+    ))
+}
+
+/// Used for buttons and select menus declared by a tag
+#[derive(Clone, Debug)]
+pub struct TagComponentsMetadata {
+    pub tag_name: String,
+    /// Owner of the tag, whose personal tags are used when the tag gets the contents of another
+    /// one, no matter who used the component
+    pub owner: u64,
+    /// Owner of the tag, if it is a personal tag
+    pub personal_owner: Option<u64>,
+    pub guild_id: Option<u64>,
+    pub channel_id: u64,
+    /// Maps the custom ID of each button to the payload the tag is ran with
+    pub button_payloads: HashMap<String, String>,
+    pub select_cid: String,
+    /// Payloads of the select menu options, indexed by option value
+    pub select_payloads: Vec<String>,
+}
+impl TagComponentsMetadata {
+    pub async fn component_callback(&mut self, data: &ComponentInteractionData) -> anyhow::Result<()> {
+        // running the tag may take longer than the interaction response window
+        data.assyst
+            .interaction_client()
+            .create_response(
+                data.interaction_id,
+                &data.interaction_token,
+                &InteractionResponse {
+                    kind: InteractionResponseType::DeferredUpdateMessage,
+                    data: None,
+                },
+            )
+            .await?;
+
+        // the interaction has been responded to, so errors have to be shown by updating the response
+        if let Err(e) = self.rerun_tag(data).await {
+            data.assyst
+                .interaction_client()
+                .update_response(&data.interaction_token)
+                .content(Some(&format!(":warning: ``{e:#}``")))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs the tag again with the payload of the used component, and updates the deferred response
+    /// with its output.
+    async fn rerun_tag(&self, data: &ComponentInteractionData) -> anyhow::Result<()> {
+        let payload = if data.custom_id == self.select_cid {
+            let value = data
+                .message_interaction_data
+                .as_ref()
+                .and_then(|d| d.values.first())
+                .context("No option was selected.")?;
+
+            value
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| self.select_payloads.get(i))
+                .context("Unknown select menu option.")?
+        } else {
+            self.button_payloads
+                .get(&data.custom_id)
+                .context("Unknown tag button.")?
+        };
+
+        let tag_data = match (self.personal_owner, self.guild_id) {
+            (Some(owner), _) => UserTag::get(&data.assyst.database_handler, owner as i64, &self.tag_name)
+                .await
+                .context("Failed to fetch personal tag")?
+                .map(|t| t.data),
+            (None, Some(guild_id)) => Tag::get(&data.assyst.database_handler, guild_id as i64, &self.tag_name)
+                .await
+                .context("Failed to fetch tag")?
+                .map(|t| t.data),
+            (None, None) => None,
+        }
+        .context("This tag no longer exists.")?;
+
+        let author = data
+            .assyst
+            .http_client
+            .user(data.invocation_user_id)
+            .await
+            .context("Failed to fetch user")?
+            .model()
+            .await?;
+
+        let tcx = TagContext {
+            tokio: Handle::current(),
+            message: None,
+            assyst: data.assyst.clone(),
+            guild_id: self.guild_id,
+            channel_id: self.channel_id,
+            author,
+            owner: self.owner,
+        };
+        let arguments = payload.split_whitespace().map(ToOwned::to_owned).collect::<Vec<_>>();
+
+        if self.personal_owner.is_none()
+            && let Some(guild_id) = self.guild_id
+        {
+            record_tag_use(
+                data.assyst.clone(),
+                guild_id as i64,
+                self.tag_name.clone(),
+                data.invocation_user_id.get() as i64,
+            );
+        }
+
+        let budget_key = (
+            self.personal_owner
+                .or(self.guild_id)
+                .unwrap_or(data.invocation_user_id.get()),
+            self.tag_name.clone(),
+        );
+        let (res, source) = run_tag(tcx, tag_data, arguments, budget_key).await;
+
+        let content;
+        let attachments;
+        let rows;
+        let client = data.assyst.interaction_client();
+        let mut update = client.update_response(&data.interaction_token);
+
+        match res {
+            Ok(ParseResult {
+                output,
+                attachment,
+                components,
+                diagnostics,
+                ..
+            }) => {
+                let output = output_with_diagnostics(output, &diagnostics);
+                let built = tag_components(
+                    data.assyst.clone(),
+                    &components,
+                    self.tag_name.clone(),
+                    self.owner,
+                    self.personal_owner,
+                    self.guild_id,
+                    self.channel_id,
+                );
+
+                content = if output.trim().is_empty() && attachment.is_none() {
+                    "[Empty Response]".to_owned()
+                } else {
+                    output
+                };
+                update = update.content(Some(&content));
+
+                if let Some((buf, _)) = attachment {
+                    let Attachment { name, data: buf } = Image(buf).into();
+                    attachments = [TwilightAttachment::from_bytes(name.into(), buf, 0)];
+                    update = update.attachments(&attachments);
+                } else {
+                    update = update.attachments(&[]);
+                }
+
+                match built {
+                    Some((components, (cids, cx))) => {
+                        rows = components;
+                        update = update.components(Some(&rows));
+
+                        let wrapped = Arc::new(Mutex::new(cx));
+                        for cid in cids {
+                            data.assyst.component_contexts.insert(&cid, &wrapped);
+                        }
+                    },
+                    None => {
+                        update = update.components(Some(&[]));
+                    },
+                }
+            },
+            Err(err) => {
+                content = assyst_tag::errors::format_error(&source, err).codeblock("ansi");
+                update = update.content(Some(&content)).components(Some(&[]));
+            },
+        }
+
+        update.await?;
+
+        Ok(())
+    }
+}
+
+struct TagContext {
+    tokio: Handle,
+    message: Option<Message>,
+    assyst: ThreadSafeAssyst,
+    /// The server the tag is ran in, or `None` in DMs
+    guild_id: Option<u64>,
+    channel_id: u64,
+    author: twilight_model::user::User,
+    /// Owner of the tag being ran, whose personal tags are found by `get_tag_contents`
+    owner: u64,
+}
+
+impl TagContext {
+    fn guild_id(&self) -> Option<u64> {
+        self.guild_id
+    }
+}
+
+impl assyst_tag::Context for TagContext {
+    fn execute_javascript(
+        &self,
+        code: &str,
+        args: Vec<String>,
+    ) -> anyhow::Result<assyst_common::eval::FakeEvalImageResponse> {
+        self.tokio.block_on(fake_eval(
+            &self.assyst.reqwest_client,
+            code.into(),
+            true,
+            self.message.as_ref(),
+            args,
+        ))
+    }
+
+    fn get_last_attachment(&self) -> anyhow::Result<String> {
+        let ImageUrl(attachment) = self.tokio.block_on(ImageUrl::from_channel_history(
+            &self.assyst,
+            Id::<ChannelMarker>::new(self.channel_id),
+        ))?;
+        Ok(attachment)
+    }
+
+    fn get_avatar(&self, user_id: Option<u64>) -> anyhow::Result<String> {
+        let user_id = user_id.unwrap_or(self.author.id.get());
+
+        self.tokio.block_on(async {
+            let user = self.assyst.http_client.user(Id::new(user_id)).await?;
+            ensure!(user.status().get() != 404, "user not found");
+
+            let user = user.model().await?;
+
+            Ok(get_avatar_url(&user))
+        })
+    }
+
+    fn download(&self, url: &str) -> anyhow::Result<String> {
+        self.tokio
+            .block_on(download_content(
+                &self.assyst.reqwest_client,
+                url,
+                ABSOLUTE_INPUT_FILE_SIZE_LIMIT_BYTES,
+                true,
+            ))
+            .map(string_from_likely_utf8)
+            .map_err(Into::into)
+    }
+
+    fn channel_id(&self) -> anyhow::Result<u64> {
+        Ok(self.channel_id)
+    }
+
+    fn guild_id(&self) -> anyhow::Result<u64> {
+        TagContext::guild_id(self).context("This tag is not being ran in a server")
+    }
+
+    fn user_id(&self) -> anyhow::Result<u64> {
+        Ok(self.author.id.get())
+    }
+
+    fn user_tag(&self, id: Option<u64>) -> anyhow::Result<String> {
+        if let Some(id) = id {
+            self.tokio.block_on(async {
+                let user = self.assyst.http_client.user(Id::new(id)).await?;
+                ensure!(user.status().get() != 404, "user not found");
+
+                Ok(format_tag(&user.model().await?))
+            })
+        } else {
+            Ok(format_tag(&self.author))
+        }
+    }
+
+    fn get_tag_contents(&self, tag: &str) -> anyhow::Result<String> {
+        // same precedence as running a tag: server tags first, then personal tags of the owner of the
+        // running tag, so that the result doesn't depend on who ran it
+        let tag = self.tokio.block_on(async {
+            if let Some(guild_id) = TagContext::guild_id(self)
+                && let Some(Tag { data, .. }) = Tag::get(&self.assyst.database_handler, guild_id as i64, tag).await?
+            {
+                return Ok(Some(data));
+            }
+
+            UserTag::get(&self.assyst.database_handler, self.owner as i64, tag)
+                .await
+                .map(|t| t.map(|t| t.data))
+                .map_err(anyhow::Error::from)
+        });
+
+        match tag {
+            Ok(Some(data)) => Ok(data),
+            Ok(None) => Err(anyhow!("Tag not found")),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+define_commandgroup! {
+    name: tag,
+    access: Availability::Public,
+    category: Category::Misc,
+    aliases: ["t"],
+    cooldown: Duration::from_secs(2),
+    description: "assyst's tag system (documentation: https://jacher.io/tags)",
+    usage: "[subcommand|tag name] <arguments...>",
+    commands: [
+        "create" => create,
+        "edit" => edit,
+        "delete" => delete,
+        "list" => list,
+        "info" => info,
+        "raw" => raw,
+        "search" => search,
+        "backup" => backup,
+        "restore" => restore,
+        "copy" => copy,
+        "paste" => paste,
+        "history" => history,
+        "diff" => diff,
+        "rollback" => rollback,
+        "transfer" => transfer,
+        "collaborators" => collaborators,
+        "alias" => alias,
+        "publish" => publish,
+        "unpublish" => unpublish,
+        "library" => library,
+        "install" => install,
+        "update" => update,
+        "top" => top,
+        "personal" => personal,
+        "report" => report,
+        "reports" => reports,
+        "unlock" => unlock
+    ],
+    default_interaction_subcommand: "run",
+    default: default
+}
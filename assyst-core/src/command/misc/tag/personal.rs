@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use anyhow::{Context, bail, ensure};
+use assyst_common::util::unix_timestamp;
+use assyst_database::model::user_tag::UserTag;
+use assyst_proc_macro::command;
+use assyst_string_fmt::Markdown;
+
+use super::{RESERVED_NAMES, run_tag_and_reply};
+use crate::command::arguments::{RestNoFlags, Word};
+use crate::command::messagebuilder::Attachment;
+use crate::command::{Availability, Category, CommandCtxt};
+
+#[command(
+    description = "manage and run your personal tags, which can be used in any server or DM",
+    aliases = ["me"],
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[create|edit|delete|raw|list|run] <name> <contents|arguments...>",
+    examples = ["create hello Hello {user}!", "run hello", "list"],
+    group_parent_name = "tag"
+)]
+pub async fn personal(
+    ctxt: CommandCtxt<'_>,
+    action: Word,
+    name: Option<Word>,
+    rest: Option<RestNoFlags>,
+) -> anyhow::Result<()> {
+    let user_id = ctxt.data.author.id.get() as i64;
+    let action = action.0.to_ascii_lowercase();
+
+    if action == "list" {
+        let tags = UserTag::get_for_user(&ctxt.assyst().database_handler, user_id)
+            .await
+            .context("Failed to fetch personal tags")?;
+
+        ensure!(!tags.is_empty(), "You don't have any personal tags.");
+
+        let message = format!(
+            "🗒️ **Your personal tags** ({} total)\nRun one anywhere with `{}t <name>`\n\n{}",
+            tags.len(),
+            ctxt.data.calling_prefix,
+            tags.iter().map(|t| t.name.codestring()).collect::<Vec<_>>().join(", ")
+        );
+
+        if message.len() > 1900 {
+            ctxt.reply(Attachment {
+                name: "personal-tags.txt".into(),
+                data: tags
+                    .into_iter()
+                    .map(|t| t.name)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+                    .into_bytes(),
+            })
+            .await?;
+        } else {
+            ctxt.reply(message).await?;
+        }
+
+        return Ok(());
+    }
+
+    let name = name
+        .context("Please provide the name of a personal tag.")?
+        .0
+        .to_ascii_lowercase();
+
+    match &action[..] {
+        "create" | "add" => {
+            ensure!(name.len() < 20, "Tag names cannot exceed 20 characters.");
+            ensure!(
+                !RESERVED_NAMES.contains(&&name[..]),
+                "Tag names cannot be a reserved word."
+            );
+
+            let tag = UserTag {
+                user_id,
+                name,
+                data: rest.context("Please provide the contents of the tag.")?.0,
+                created_at: unix_timestamp() as i64,
+            };
+
+            let success = tag
+                .set(&ctxt.assyst().database_handler)
+                .await
+                .context("Failed to create personal tag")?;
+
+            ensure!(success, "You already have a personal tag with that name.");
+
+            ctxt.reply(format!("Successfully created personal tag {}", tag.name.codestring()))
+                .await?;
+        },
+        "edit" => {
+            let contents = rest.context("Please provide the new contents of the tag.")?.0;
+
+            let success = UserTag::edit(&ctxt.assyst().database_handler, user_id, &name, &contents)
+                .await
+                .context("Failed to edit personal tag")?;
+
+            ensure!(success, "You don't have a personal tag with that name.");
+
+            ctxt.reply(format!("Successfully edited personal tag {}", name.codestring()))
+                .await?;
+        },
+        "delete" | "remove" => {
+            let success = UserTag::delete(&ctxt.assyst().database_handler, user_id, &name)
+                .await
+                .context("Failed to delete personal tag")?;
+
+            ensure!(success, "You don't have a personal tag with that name.");
+
+            ctxt.reply(format!("Successfully deleted personal tag {}", name.codestring()))
+                .await?;
+        },
+        "raw" => {
+            let tag = UserTag::get(&ctxt.assyst().database_handler, user_id, &name)
+                .await
+                .context("Failed to fetch personal tag")?
+                .context("You don't have a personal tag with that name.")?;
+
+            ctxt.reply(Attachment {
+                name: format!("tag-{}.txt", tag.name).into_boxed_str(),
+                data: tag.data.into_bytes(),
+            })
+            .await?;
+        },
+        // runs a personal tag even if a server tag shares its name
+        "run" => {
+            let tag = UserTag::get(&ctxt.assyst().database_handler, user_id, &name)
+                .await
+                .context("Failed to fetch personal tag")?
+                .context("You don't have a personal tag with that name.")?;
+
+            let arguments = rest
+                .map(|r| r.0.split_whitespace().map(ToOwned::to_owned).collect::<Vec<_>>())
+                .unwrap_or_default();
+
+            run_tag_and_reply(
+                &ctxt,
+                tag.name,
+                tag.data,
+                user_id as u64,
+                Some(user_id as u64),
+                arguments,
+            )
+            .await?;
+        },
+        _ => bail!(
+            "Unknown action {}. Valid actions are create, edit, delete, raw, list and run.",
+            action.codestring()
+        ),
+    }
+
+    Ok(())
+}
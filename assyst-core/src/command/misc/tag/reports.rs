@@ -0,0 +1,353 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use anyhow::{Context, bail, ensure};
+use assyst_common::util::discord::format_discord_timestamp;
+use assyst_common::util::unix_timestamp;
+use assyst_database::DatabaseHandler;
+use assyst_database::model::tag::Tag;
+use assyst_database::model::tag_lock::TagLock;
+use assyst_database::model::tag_report::TagReport;
+use assyst_proc_macro::command;
+use assyst_string_fmt::Markdown;
+use twilight_model::channel::message::Component;
+use twilight_model::channel::message::component::ButtonStyle;
+use twilight_model::http::interaction::{InteractionResponse, InteractionResponseType};
+use twilight_model::id::Id;
+use twilight_model::id::marker::UserMarker;
+
+use crate::command::arguments::{RestNoFlags, WordAutocomplete};
+use crate::command::componentctxt::{ComponentCtxt, ComponentInteractionData, ComponentMetadata, button_new};
+use crate::command::messagebuilder::MessageBuilder;
+use crate::command::{Availability, Category, CommandCtxt};
+
+const MAX_REPORT_REASON_LENGTH: usize = 200;
+const MAX_REVIEW_REPORTS: usize = 5;
+const MAX_REVIEW_PREVIEW_LENGTH: usize = 500;
+
+    ensure!(
+        lock.is_none(),
+        "Tag {} has been locked by a server manager and can't be edited.",
+        name.codestring()
+    );
+
+    Ok(())
+}
+
+#[command(
+    description = "report a tag to the server managers",
+    cooldown = Duration::from_secs(10),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[name] [reason]",
+    examples = ["test spamming pings"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn report(
+    ctxt: CommandCtxt<'_>,
+    #[autocomplete = "crate::command::misc::tag::tag_names_autocomplete"] name: WordAutocomplete,
+    reason: RestNoFlags,
+) -> anyhow::Result<()> {
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tags can only be reported in guilds.")
+    };
+
+    ensure!(
+        reason.0.len() <= MAX_REPORT_REASON_LENGTH,
+        "Report reasons cannot exceed {MAX_REPORT_REASON_LENGTH} characters."
+    );
+
+    let tag = Tag::get(
+        &ctxt.assyst().database_handler,
+        guild_id.get() as i64,
+        &name.0.to_ascii_lowercase(),
+    )
+    .await?
+    .context("Tag not found in this server.")?;
+
+    let report = TagReport {
+        guild_id: tag.guild_id,
+        name: tag.name,
+        reporter: ctxt.data.author.id.get() as i64,
+        reason: reason.0,
+        created_at: unix_timestamp() as i64,
+    };
+
+    let success = report
+        .set(&ctxt.assyst().database_handler)
+        .await
+        .context("Failed to report tag")?;
+
+    ensure!(success, "You have already reported this tag.");
+
+    ctxt.reply(format!(
+        "Tag {} has been reported to the server managers.",
+        report.name.codestring()
+    ))
+    .await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "review reported tags in the server",
+    cooldown = Duration::from_secs(2),
+    access = Availability::ServerManagers,
+    category = Category::Misc,
+    usage = "",
+    examples = [""],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn reports(ctxt: CommandCtxt<'_>) -> anyhow::Result<()> {
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tag reports can only be reviewed in guilds.")
+    };
+
+    let timestamp = unix_timestamp();
+    let mut review = TagReviewComponentMetadata {
+        guild_id: guild_id.get() as i64,
+        invocating_user_id: ctxt.data.author.id,
+        approve_cid: format!("tag_review-approve-{timestamp}"),
+        delete_cid: format!("tag_review-delete-{timestamp}"),
+        lock_cid: format!("tag_review-lock-{timestamp}"),
+        skip_cid: format!("tag_review-skip-{timestamp}"),
+        current: None,
+        skipped: Vec::new(),
+    };
+
+    let message = review
+        .next_message(&ctxt.assyst().database_handler)
+        .await?
+        .context("There are no reported tags in this server.")?;
+
+    ctxt.reply(MessageBuilder {
+        content: Some(message),
+        attachment: None,
+        components: Some(vec![
+            Component::Button(button_new(&review.approve_cid, "Approve", ButtonStyle::Success)),
+            Component::Button(button_new(&review.delete_cid, "Delete", ButtonStyle::Danger)),
+            Component::Button(button_new(&review.lock_cid, "Lock", ButtonStyle::Primary)),
+            Component::Button(button_new(&review.skip_cid, "Skip", ButtonStyle::Secondary)),
+        ]),
+        component_ctxt: Some((
+            vec![
+                review.approve_cid.clone(),
+                review.delete_cid.clone(),
+                review.lock_cid.clone(),
+                review.skip_cid.clone(),
+            ],
+            ComponentCtxt::new(ctxt.assyst().clone(), ComponentMetadata::TagReview(review)),
+        )),
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Used for the buttons of the tag report review queue
+#[derive(Clone, Debug)]
+pub struct TagReviewComponentMetadata {
+    pub guild_id: i64,
+    pub invocating_user_id: Id<UserMarker>,
+    pub approve_cid: String,
+    pub delete_cid: String,
+    pub lock_cid: String,
+    pub skip_cid: String,
+    /// The reported tag currently being reviewed
+    pub current: Option<String>,
+    /// Tags skipped during this review, which won't be shown again
+    pub skipped: Vec<String>,
+}
+impl TagReviewComponentMetadata {
+    /// Moves on to the oldest reported tag that hasn't been skipped, returning the message
+    /// describing it, or `None` if there are no more reported tags.
+    async fn next_message(&mut self, handler: &DatabaseHandler) -> anyhow::Result<Option<String>> {
+        let reports = TagReport::get_for_guild(handler, self.guild_id)
+            .await
+            .context("Failed to fetch tag reports")?;
+
+        let mut pending = Vec::<&str>::new();
+        for report in &reports {
+            if !self.skipped.contains(&report.name) && !pending.contains(&&report.name[..]) {
+                pending.push(&report.name);
+            }
+        }
+
+        for (index, name) in pending.iter().enumerate() {
+            // the tag may have been renamed or deleted since it was reported
+            let Some(tag) = Tag::get(handler, self.guild_id, name).await? else {
+                TagReport::delete_for_tag(handler, self.guild_id, name)
+                    .await
+                    .context("Failed to resolve tag reports")?;
+                continue;
+            };
+
+            let locked = TagLock::get(handler, self.guild_id, &tag.name)
+                .await
+                .context("Failed to fetch tag lock")?
+                .is_some();
+
+            let mut message = format!(
+                "🚩 **Reported tag: **{} ({} of {} in queue)\n\nAuthor: <@{}>\nLocked: {}\n\n**Reports:**\n",
+                tag.name.codestring(),
+                index + 1,
+                pending.len(),
+                tag.author,
+                if locked { "yes" } else { "no" }
+            );
+
+            for report in reports.iter().filter(|r| r.name == tag.name).take(MAX_REVIEW_REPORTS) {
+                writeln!(
+                    message,
+                    "<@{}> {}: {}",
+                    report.reporter,
+                    format_discord_timestamp(report.created_at as u64),
+                    report.reason
+                )?;
+            }
+
+            write!(
+                message,
+                "\n{}",
+                tag.data
+                    .chars()
+                    .take(MAX_REVIEW_PREVIEW_LENGTH)
+                    .collect::<String>()
+                    .codeblock("")
+            )?;
+
+            self.current = Some(tag.name);
+            return Ok(Some(message));
+        }
+
+        self.current = None;
+        Ok(None)
+    }
+
+    pub async fn component_callback(&mut self, data: &ComponentInteractionData) -> anyhow::Result<()> {
+        if data.invocation_user_id != self.invocating_user_id {
+            bail!("This command was not ran by you.");
+        }
+
+        let name = self
+            .current
+            .clone()
+            .context("There are no more reported tags to review.")?;
+        let handler = &data.assyst.database_handler;
+        let manager = self.invocating_user_id.get() as i64;
+
+        data.assyst
+            .interaction_client()
+            .create_response(
+                data.interaction_id,
+                &data.interaction_token,
+                &InteractionResponse {
+                    kind: InteractionResponseType::DeferredUpdateMessage,
+                    data: None,
+                },
+            )
+            .await?;
+
+        let outcome = if data.custom_id == self.approve_cid {
+            TagReport::delete_for_tag(handler, self.guild_id, &name)
+                .await
+                .context("Failed to resolve tag reports")?;
+
+            format!("Approved tag {}", name.codestring())
+        } else if data.custom_id == self.delete_cid {
+            if let Some(tag) = Tag::get(handler, self.guild_id, &name).await? {
+                Tag::delete_force(handler, &tag.name, tag.guild_id, manager)
+                    .await
+                    .context("Failed to delete tag")?;
+            }
+
+            format!("Deleted tag {}", name.codestring())
+        } else if data.custom_id == self.lock_cid {
+            TagLock {
+                guild_id: self.guild_id,
+                name: name.clone(),
+                locked_by: manager,
+                locked_at: unix_timestamp() as i64,
+            }
+            .set(handler)
+            .await
+            .context("Failed to lock tag")?;
+
+            TagReport::delete_for_tag(handler, self.guild_id, &name)
+                .await
+                .context("Failed to resolve tag reports")?;
+
+            format!("Locked tag {}", name.codestring())
+        } else {
+            self.skipped.push(name.clone());
+            format!("Skipped tag {}", name.codestring())
+        };
+
+        let client = data.assyst.interaction_client();
+        let content;
+
+        match self.next_message(handler).await? {
+            Some(next) => {
+                content = format!("{outcome}.\n\n{next}");
+                client
+                    .update_response(&data.interaction_token)
+                    .content(Some(&content))
+                    .await?;
+            },
+            None => {
+                content = format!("{outcome}. There are no more reported tags to review.");
+                client
+                    .update_response(&data.interaction_token)
+                    .content(Some(&content))
+                    .components(Some(&[]))
+                    .await?;
+            },
+        }
+
+        Ok(())
+    }
+}
+
+#[command(
+    description = "unlock a tag that was locked from the report review queue",
+    cooldown = Duration::from_secs(2),
+    access = Availability::ServerManagers,
+    category = Category::Misc,
+    usage = "[name]",
+    examples = ["test"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn unlock(
+    ctxt: CommandCtxt<'_>,
+    #[autocomplete = "crate::command::misc::tag::tag_names_autocomplete"] name: WordAutocomplete,
+) -> anyhow::Result<()> {
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tags can only be unlocked in guilds.")
+    };
+
+    // locks are kept when a tag is deleted, so names without a tag can be unlocked too
+    let name = match Tag::get(
+        &ctxt.assyst().database_handler,
+        guild_id.get() as i64,
+        &name.0.to_ascii_lowercase(),
+    )
+    .await?
+    {
+        Some(tag) => tag.name,
+        None => name.0.to_ascii_lowercase(),
+    };
+
+    let success = TagLock::delete(&ctxt.assyst().database_handler, guild_id.get() as i64, &name)
+        .await
+        .context("Failed to unlock tag")?;
+
+    ensure!(success, "That tag is not locked.");
+
+    ctxt.reply(format!("Successfully unlocked tag {}", name.codestring()))
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,468 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io::{Cursor, Read};
+use std::time::Duration;
+
+use anyhow::{Context, bail, ensure};
+use assyst_common::util::{string_from_likely_utf8, unix_timestamp};
+use assyst_database::model::tag::Tag;
+use assyst_database::model::tag_collaborator::TagCollaborator;
+use assyst_database::model::tag_lock::TagLock;
+use assyst_proc_macro::command;
+use assyst_string_fmt::Markdown;
+use twilight_model::application::interaction::application_command::CommandOptionValue;
+use twilight_model::channel::message::Component;
+use twilight_model::channel::message::component::ButtonStyle;
+use twilight_model::http::interaction::{InteractionResponse, InteractionResponseType};
+use twilight_model::id::Id;
+use twilight_model::id::marker::UserMarker;
+use twilight_util::builder::command::StringBuilder;
+use zip::ZipArchive;
+
+use super::{RESERVED_NAMES, TagBackupEntry};
+use crate::command::arguments::{Image, ParseArgument};
+use crate::command::componentctxt::{ComponentCtxt, ComponentInteractionData, ComponentMetadata, button_new};
+use crate::command::errors::TagParseError;
+use crate::command::flags::{FlagDecode, FlagType, flags_from_str};
+use crate::command::messagebuilder::MessageBuilder;
+use crate::command::{Availability, Category, CommandCtxt};
+
+const MAX_RESTORE_TAGS: usize = 500;
+const MAX_RESTORE_FILE_SIZE: u64 = 1024 * 1024;
+const MAX_RESTORE_TOTAL_SIZE: u64 = 4 * 1024 * 1024;
+const MAX_RESTORE_RENAME_ATTEMPTS: usize = 50;
+const MAX_RESTORE_NOTES: usize = 15;
+
+/// Reads the tags out of a `tag backup` archive, or out of a JSON export.\
+/// Archives without a JSON export have their tag names recovered from the file names. At most
+/// [`MAX_RESTORE_TOTAL_SIZE`] bytes are decompressed from an archive in total.
+fn read_tag_backup(buf: Vec<u8>) -> anyhow::Result<Vec<TagBackupEntry>> {
+    if buf.trim_ascii_start().starts_with(b"[") {
+        return serde_json::from_slice(&buf).context("Failed to parse tag export");
+    }
+
+    let mut archive =
+        ZipArchive::new(Cursor::new(buf)).context("The provided file is not a tag backup archive or JSON export.")?;
+
+    if let Ok(file) = archive.by_name("tags.json") {
+        let mut json = Vec::new();
+        file.take(MAX_RESTORE_TOTAL_SIZE + 1).read_to_end(&mut json)?;
+        ensure!(
+            json.len() as u64 <= MAX_RESTORE_TOTAL_SIZE,
+            "Backups can contain at most {} MiB of tags.",
+            MAX_RESTORE_TOTAL_SIZE / 1024 / 1024
+        );
+        return serde_json::from_slice(&json).context("Failed to parse the tag export in this archive");
+    }
+
+    let mut entries = Vec::new();
+    let mut total_size = 0;
+    for i in 0..archive.len() {
+        let file = archive.by_index(i)?;
+
+        // files are named tag-{index}-{name}.txt
+        let Some(name) = file
+            .name()
+            .strip_prefix("tag-")
+            .and_then(|n| n.split_once('-'))
+            .and_then(|(_, n)| n.strip_suffix(".txt"))
+            .map(ToOwned::to_owned)
+        else {
+            continue;
+        };
+
+        let mut data = Vec::new();
+        file.take(MAX_RESTORE_FILE_SIZE).read_to_end(&mut data)?;
+
+        total_size += data.len() as u64;
+        ensure!(
+            total_size <= MAX_RESTORE_TOTAL_SIZE,
+            "Backups can contain at most {} MiB of tags.",
+            MAX_RESTORE_TOTAL_SIZE / 1024 / 1024
+        );
+
+        entries.push(TagBackupEntry {
+            name,
+            data: string_from_likely_utf8(data),
+            created_at: None,
+        });
+
+        ensure!(
+            entries.len() <= MAX_RESTORE_TAGS,
+            "Backups can contain at most {MAX_RESTORE_TAGS} tags."
+        );
+    }
+
+    Ok(entries)
+}
+
+/// What to do with a restored tag whose name is already taken.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TagRestoreStrategy {
+    #[default]
+    Skip,
+    Overwrite,
+    Rename,
+}
+impl TagRestoreStrategy {
+    fn from_name(name: &str) -> anyhow::Result<Self> {
+        match name {
+            "skip" => Ok(Self::Skip),
+            "overwrite" => Ok(Self::Overwrite),
+            "rename" => Ok(Self::Rename),
+            _ => bail!("Unknown restore strategy {name} (expected skip, overwrite or rename)"),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::Overwrite => "overwrite",
+            Self::Rename => "rename",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TagRestoreFlags {
+    pub strategy: TagRestoreStrategy,
+}
+impl FlagDecode for TagRestoreFlags {
+    fn from_str(input: &str) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut valid_flags = HashMap::new();
+        valid_flags.insert("strategy", FlagType::WithValue);
+
+        let raw_decode = flags_from_str(input, valid_flags)?;
+        let strategy = raw_decode
+            .get("strategy")
+            .and_then(|x| x.as_deref())
+            .map_or(Ok(TagRestoreStrategy::default()), TagRestoreStrategy::from_name)?;
+
+        Ok(Self { strategy })
+    }
+}
+impl ParseArgument for TagRestoreFlags {
+    fn as_command_options(_: &str) -> Vec<twilight_model::application::command::CommandOption> {
+        vec![
+            StringBuilder::new("strategy", "what to do with tags whose names are already taken")
+                .required(false)
+                .choices(vec![("skip", "skip"), ("overwrite", "overwrite"), ("rename", "rename")])
+                .build(),
+        ]
+    }
+
+    async fn parse_raw_message(
+        ctxt: &mut crate::command::RawMessageParseCtxt<'_>,
+        label: crate::command::Label,
+    ) -> Result<Self, crate::command::errors::TagParseError> {
+        let args = ctxt.rest_all(label);
+        let parsed = Self::from_str(&args).map_err(TagParseError::FlagParseError)?;
+        Ok(parsed)
+    }
+
+    async fn parse_command_option(
+        ctxt: &mut crate::command::InteractionCommandParseCtxt<'_>,
+        _: crate::command::Label,
+    ) -> Result<Self, TagParseError> {
+        let strategy = match ctxt.option_by_name("strategy").map(|o| &o.value) {
+            Ok(CommandOptionValue::String(strategy)) => {
+                TagRestoreStrategy::from_name(strategy).map_err(TagParseError::FlagParseError)?
+            },
+            _ => TagRestoreStrategy::default(),
+        };
+
+        Ok(Self { strategy })
+    }
+}
+
+/// A tag that will be written once a restore is confirmed.
+#[derive(Clone, Debug)]
+pub struct PlannedTagRestore {
+    pub name: String,
+    pub data: String,
+    /// Whether this replaces the contents of an existing tag
+    pub overwrite: bool,
+}
+
+#[command(
+    description = "restore tags from a tag backup archive or JSON export",
+    cooldown = Duration::from_secs(5),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[backup file] <flags>",
+    examples = ["https://example.com/tags.zip", "https://example.com/tags.zip --strategy rename"],
+    flag_descriptions = [("strategy [skip|overwrite|rename]", "what to do with tags whose names are already taken (default: skip)")],
+    send_processing = true,
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn restore(ctxt: CommandCtxt<'_>, file: Image, flags: TagRestoreFlags) -> anyhow::Result<()> {
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tags can only be restored in guilds.")
+    };
+
+    let guild_id = guild_id.get() as i64;
+    let author = ctxt.data.author.id.get() as i64;
+    let handler = &ctxt.assyst().database_handler;
+
+    let entries = read_tag_backup(file.0)?;
+    ensure!(!entries.is_empty(), "The provided backup doesn't contain any tags.");
+    ensure!(
+        entries.len() <= MAX_RESTORE_TAGS,
+        "Backups can contain at most {MAX_RESTORE_TAGS} tags."
+    );
+
+    let mut planned = Vec::<PlannedTagRestore>::new();
+    let mut notes = Vec::new();
+    let mut skipped = 0;
+
+    for entry in entries {
+        let name = entry.name.to_ascii_lowercase();
+
+        if name.is_empty() || name.len() >= 20 || RESERVED_NAMES.contains(&&name[..]) || name.contains(' ') {
+            notes.push(format!("{}: skipped (invalid name)", name.codestring()));
+            skipped += 1;
+            continue;
+        }
+
+        let existing = Tag::get(handler, guild_id, &name)
+            .await
+            .context("Failed to fetch tag")?;
+        let taken = existing.is_some() || planned.iter().any(|p| p.name == name);
+
+        let (name, overwrite) = if !taken {
+            (name, false)
+        } else {
+            match flags.strategy {
+                TagRestoreStrategy::Skip => {
+                    notes.push(format!("{}: skipped (already exists)", name.codestring()));
+                    skipped += 1;
+                    continue;
+                },
+                TagRestoreStrategy::Overwrite => {
+                    // aliases and tags from earlier in the backup can't be overwritten
+                    let Some(existing) = existing.filter(|t| t.name == name) else {
+                        notes.push(format!("{}: skipped (name is taken)", name.codestring()));
+                        skipped += 1;
+                        continue;
+                    };
+
+                    let can_edit = existing.author == author
+                        || TagCollaborator::get_for_tag(handler, guild_id, &name)
+                            .await
+                            .context("Failed to fetch tag collaborators")?
+                            .iter()
+                            .any(|c| c.user_id == author);
+
+                    if !can_edit || planned.iter().any(|p| p.name == name) {
+                        notes.push(format!("{}: skipped (not editable by you)", name.codestring()));
+                        skipped += 1;
+                        continue;
+                    }
+
+                    if TagLock::get(handler, guild_id, &name)
+                        .await
+                        .context("Failed to fetch tag lock")?
+                        .is_some()
+                    {
+                        notes.push(format!("{}: skipped (locked)", name.codestring()));
+                        skipped += 1;
+                        continue;
+                    }
+
+                    (name, true)
+                },
+                TagRestoreStrategy::Rename => {
+                    let mut renamed = None;
+                    for i in 1..=MAX_RESTORE_RENAME_ATTEMPTS {
+                        let candidate = format!("{name}-{i}");
+                        if candidate.len() >= 20 {
+                            break;
+                        }
+
+                        if !planned.iter().any(|p| p.name == candidate)
+                            && Tag::get(handler, guild_id, &candidate)
+                                .await
+                                .context("Failed to fetch tag")?
+                                .is_none()
+                        {
+                            renamed = Some(candidate);
+                            break;
+                        }
+                    }
+
+                    let Some(renamed) = renamed else {
+                        notes.push(format!("{}: skipped (no free name found)", name.codestring()));
+                        skipped += 1;
+                        continue;
+                    };
+
+                    notes.push(format!("{} → {}", name.codestring(), renamed.codestring()));
+                    (renamed, false)
+                },
+            }
+        };
+
+        planned.push(PlannedTagRestore {
+            name,
+            data: entry.data,
+            overwrite,
+        });
+    }
+
+    let overwrites = planned.iter().filter(|p| p.overwrite).count();
+    let mut message = format!(
+        "🗒️ **Tag restore summary** (strategy: {})\n\nTo create: {}\nTo overwrite: {}\nSkipped: {}",
+        flags.strategy.as_str(),
+        planned.len() - overwrites,
+        overwrites,
+        skipped
+    );
+
+    if !notes.is_empty() {
+        message.push('\n');
+        for note in notes.iter().take(MAX_RESTORE_NOTES) {
+            write!(message, "\n{note}")?;
+        }
+        if notes.len() > MAX_RESTORE_NOTES {
+            write!(message, "\n...and {} more", notes.len() - MAX_RESTORE_NOTES)?;
+        }
+    }
+
+    if planned.is_empty() {
+        message.push_str("\n\nThere is nothing to restore.");
+        ctxt.reply(message).await?;
+        return Ok(());
+    }
+
+    message.push_str("\n\nNothing has been changed yet. Press **Restore** to apply these changes.");
+
+    let timestamp = unix_timestamp();
+    let confirm_cid = format!("tag_restore-confirm-{timestamp}");
+    let cancel_cid = format!("tag_restore-cancel-{timestamp}");
+
+    ctxt.reply(MessageBuilder {
+        content: Some(message),
+        attachment: None,
+        components: Some(vec![
+            Component::Button(button_new(&confirm_cid, "Restore", ButtonStyle::Success)),
+            Component::Button(button_new(&cancel_cid, "Cancel", ButtonStyle::Danger)),
+        ]),
+        component_ctxt: Some((
+            vec![confirm_cid.clone(), cancel_cid.clone()],
+            ComponentCtxt::new(
+                ctxt.assyst().clone(),
+                ComponentMetadata::TagRestore(TagRestoreComponentMetadata {
+                    guild_id,
+                    invocating_user_id: ctxt.data.author.id,
+                    confirm_cid,
+                    cancel_cid,
+                    planned,
+                    handled: false,
+                }),
+            ),
+        )),
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Used for the confirmation buttons of a tag restore
+#[derive(Clone, Debug)]
+pub struct TagRestoreComponentMetadata {
+    pub guild_id: i64,
+    pub invocating_user_id: Id<UserMarker>,
+    pub confirm_cid: String,
+    pub cancel_cid: String,
+    pub planned: Vec<PlannedTagRestore>,
+    /// Set once the restore has been confirmed or cancelled, so that it can't be applied twice
+    pub handled: bool,
+}
+impl TagRestoreComponentMetadata {
+    pub async fn component_callback(&mut self, data: &ComponentInteractionData) -> anyhow::Result<()> {
+        if data.invocation_user_id != self.invocating_user_id {
+            bail!("This command was not ran by you.");
+        }
+
+        ensure!(!self.handled, "This restore has already been handled.");
+        self.handled = true;
+
+        // writing many tags may take longer than the interaction response window
+        data.assyst
+            .interaction_client()
+            .create_response(
+                data.interaction_id,
+                &data.interaction_token,
+                &InteractionResponse {
+                    kind: InteractionResponseType::DeferredUpdateMessage,
+                    data: None,
+                },
+            )
+            .await?;
+
+        let content = if data.custom_id == self.cancel_cid {
+            "Tag restore cancelled. Nothing has been changed.".to_owned()
+        } else {
+            let handler = &data.assyst.database_handler;
+            let author = self.invocating_user_id.get() as i64;
+            let mut created = 0;
+            let mut overwritten = 0;
+            let mut failed = 0;
+
+            for planned in &self.planned {
+                let success = if planned.overwrite {
+                    Tag::edit(handler, author, self.guild_id, &planned.name, &planned.data)
+                        .await
+                        .context("Failed to edit tag")?
+                } else {
+                    Tag {
+                        name: planned.name.clone(),
+                        data: planned.data.clone(),
+                        author,
+                        guild_id: self.guild_id,
+                        // the export is user-supplied, so its creation times can't be trusted
+                        created_at: unix_timestamp() as i64,
+                    }
+                    .set(handler)
+                    .await
+                    .context("Failed to create tag")?
+                };
+
+                // the name may have been taken since the summary was made
+                if !success {
+                    failed += 1;
+                    continue;
+                }
+
+                if planned.overwrite {
+                    overwritten += 1;
+                } else {
+                    created += 1;
+                }
+            }
+
+            let mut content = format!("Restored tags: {created} created, {overwritten} overwritten.");
+            if failed > 0 {
+                write!(
+                    content,
+                    " {failed} could not be written as they changed since the summary was made."
+                )?;
+            }
+
+            content
+        };
+
+        data.assyst
+            .interaction_client()
+            .update_response(&data.interaction_token)
+            .content(Some(&content))
+            .components(Some(&[]))
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,226 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use anyhow::{Context, bail, ensure};
+use assyst_common::util::discord::format_discord_timestamp;
+use assyst_common::util::unix_timestamp;
+use assyst_database::model::tag::Tag;
+use assyst_database::model::tag_alias::TagAlias;
+use assyst_database::model::tag_revision::{TagRevision, TagRevisionKind};
+use assyst_proc_macro::command;
+use assyst_string_fmt::Markdown;
+use similar::TextDiff;
+
+use super::{DEFAULT_LIST_COUNT, ensure_not_locked};
+use crate::command::arguments::WordAutocomplete;
+use crate::command::messagebuilder::Attachment;
+use crate::command::{Availability, Category, CommandCtxt};
+
+#[command(
+    description = "view the revision history of a tag",
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[name] <page>",
+    examples = ["test", "script 2"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn history(
+    ctxt: CommandCtxt<'_>,
+    #[autocomplete = "crate::command::misc::tag::tag_names_autocomplete"] name: WordAutocomplete,
+    page: Option<u64>,
+) -> anyhow::Result<()> {
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tag history can only be fetched in guilds.")
+    };
+
+    let page = page.unwrap_or(1);
+    ensure!(page >= 1, "Page must be greater or equal to 1");
+
+    let name = name.0.to_ascii_lowercase();
+    let name = TagAlias::get(&ctxt.assyst().database_handler, guild_id.get() as i64, &name)
+        .await?
+        .map_or(name, |a| a.target);
+    let offset = (page as i64 - 1) * DEFAULT_LIST_COUNT;
+
+    let revisions = TagRevision::get_paged(
+        &ctxt.assyst().database_handler,
+        guild_id.get() as i64,
+        &name,
+        offset,
+        DEFAULT_LIST_COUNT,
+    )
+    .await
+    .context("Failed to fetch tag revisions")?;
+
+    ensure!(!revisions.is_empty(), "No revisions found for that tag.");
+
+    let mut message = format!(
+        "🗒️ **Revision history for tag {}**\nCompare a revision to the current tag with `{}t diff {name} <revision>`\n\n",
+        name.codestring(),
+        ctxt.data.calling_prefix
+    );
+
+    for revision in &revisions {
+        writeln!(
+            message,
+            "{}. {} by <@{}> {}",
+            revision.revision,
+            revision.kind,
+            revision.author,
+            format_discord_timestamp(revision.created_at as u64)
+        )?;
+    }
+
+    write!(message, "\nShowing {} revisions (page {page})", revisions.len())?;
+
+    ctxt.reply(message).await?;
+
+    Ok(())
+}
+
+#[command(
+    description = "compare a previous revision of a tag to its current contents",
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[name] [revision]",
+    examples = ["test 1", "script 3"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn diff(
+    ctxt: CommandCtxt<'_>,
+    #[autocomplete = "crate::command::misc::tag::tag_names_autocomplete"] name: WordAutocomplete,
+    revision: u64,
+) -> anyhow::Result<()> {
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tag revisions can only be compared in guilds.")
+    };
+
+    let name = name.0.to_ascii_lowercase();
+    let name = TagAlias::get(&ctxt.assyst().database_handler, guild_id.get() as i64, &name)
+        .await?
+        .map_or(name, |a| a.target);
+
+    let old = TagRevision::get(
+        &ctxt.assyst().database_handler,
+        guild_id.get() as i64,
+        &name,
+        revision as i64,
+    )
+    .await
+    .context("Failed to fetch tag revision")?
+    .context("That revision does not exist.")?;
+
+    let current = Tag::get(&ctxt.assyst().database_handler, guild_id.get() as i64, &name)
+        .await
+        .context("Failed to fetch tag")?
+        .map(|t| t.data)
+        .unwrap_or_default();
+
+    let diff = TextDiff::from_lines(&old.data, &current)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("revision {revision}"), "current")
+        .to_string();
+
+    if diff.is_empty() {
+        ctxt.reply(format!("Revision {revision} is identical to the current tag."))
+            .await?;
+    } else if diff.len() > 1900 {
+        ctxt.reply(Attachment {
+            name: format!("tag-{name}-{revision}.diff").into_boxed_str(),
+            data: diff.into_bytes(),
+        })
+        .await?;
+    } else {
+        ctxt.reply(diff.codeblock("diff")).await?;
+    }
+
+    Ok(())
+}
+
+#[command(
+    description = "restore a tag to a previous revision",
+    cooldown = Duration::from_secs(2),
+    access = Availability::Public,
+    category = Category::Misc,
+    usage = "[name] [revision]",
+    examples = ["test 1", "script 3"],
+    guild_only = true,
+    group_parent_name = "tag"
+)]
+pub async fn rollback(
+    ctxt: CommandCtxt<'_>,
+    #[autocomplete = "crate::command::misc::tag::tag_names_autocomplete"] name: WordAutocomplete,
+    revision: u64,
+) -> anyhow::Result<()> {
+    let author = ctxt.data.author.id.get();
+    let Some(guild_id) = ctxt.data.guild_id else {
+        bail!("Tags can only be rolled back in guilds.")
+    };
+
+    let name = name.0.to_ascii_lowercase();
+    let handler = &ctxt.assyst().database_handler;
+    let name = TagAlias::get(handler, guild_id.get() as i64, &name)
+        .await?
+        .map_or(name, |a| a.target);
+
+    let target = TagRevision::get(handler, guild_id.get() as i64, &name, revision as i64)
+        .await
+        .context("Failed to fetch tag revision")?
+        .context("That revision does not exist.")?;
+
+    if Tag::get(handler, guild_id.get() as i64, &name)
+        .await
+        .context("Failed to fetch tag")?
+        .is_some()
+    {
+        ensure_not_locked(ctxt.assyst(), guild_id.get() as i64, &name).await?;
+
+        let success = Tag::edit(handler, author as i64, guild_id.get() as i64, &name, &target.data)
+            .await
+            .context("Failed to edit tag")?;
+
+        ensure!(success, "Failed to roll back that tag. Do you own it?");
+    } else {
+        // the tag was deleted, so restore it under its original creator
+        let creation = TagRevision::get_latest_of_kind(handler, guild_id.get() as i64, &name, TagRevisionKind::Create)
+            .await
+            .context("Failed to fetch tag revision")?
+            .context("Failed to find who created that tag.")?;
+
+        let is_manager = ctxt
+            .assyst()
+            .rest_cache_handler
+            .user_is_guild_manager(guild_id.get(), author)
+            .await
+            .context("Failed to fetch user permissions")?;
+
+        ensure!(
+            is_manager || creation.author == author as i64,
+            "Only the creator of this tag or a server manager can restore it."
+        );
+
+        let tag = Tag {
+            name: name.clone(),
+            data: target.data,
+            author: creation.author,
+            guild_id: guild_id.get() as i64,
+            created_at: unix_timestamp() as i64,
+        };
+
+        let success = tag.set(handler).await.context("Failed to restore tag")?;
+        ensure!(success, "That tag name is already used in this server.");
+    }
+
+    ctxt.reply(format!(
+        "Successfully rolled back tag {} to revision {revision}",
+        name.codestring()
+    ))
+    .await?;
+
+    Ok(())
+}
@@ -34,9 +34,11 @@ use std::time::{Duration, Instant};
 use assyst_common::config::CONFIG;
 use assyst_database::model::guild_disabled_command::GuildDisabledCommand;
 use assyst_flux_iface::FluxHandler;
+use assyst_flux_iface::queue::{QUEUE_POSITION_UPDATES, QueuePosition};
 use async_trait::async_trait;
 use autocomplete::AutocompleteData;
 use errors::TagParseError;
+use tokio::sync::mpsc;
 use twilight_model::application::command::{CommandOption, CommandOptionChoice};
 use twilight_model::application::interaction::application_command::{CommandDataOption, CommandOptionValue};
 use twilight_model::channel::{Attachment, Message};
@@ -111,6 +113,9 @@ pub struct CommandMetadata {
     pub guild_only: bool,
     /// The parent command's name, if this is part of a command group. Empty otherwise
     pub group_parent_name: &'static str,
+    /// Whether the format of the output can be chosen with `--format`. Defaults to true for image
+    /// commands, other than ones whose output is not an image or video
+    pub output_format: bool,
 }
 
 #[derive(Debug)]
@@ -433,6 +438,24 @@ impl<'a> CommandCtxt<'a> {
     pub fn flux_handler(&self) -> &'a FluxHandler {
         &self.data.assyst.flux_handler
     }
+
+    /// Drives a command to completion, editing the queue position of any Flux job it waits on into
+    /// the "Processing..." reply.
+    pub async fn with_queue_updates<T>(&self, command: impl Future<Output = T>) -> T {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let command = QUEUE_POSITION_UPDATES.scope(tx, command);
+        tokio::pin!(command);
+
+        loop {
+            tokio::select! {
+                result = &mut command => return result,
+                Some(QueuePosition { position, shown }) = rx.recv() => {
+                    let _ = self.reply(format!("Processing... (position {position} in queue)")).await;
+                    let _ = shown.send(());
+                },
+            }
+        }
+    }
 }
 
 pub async fn check_metadata(metadata: &'static CommandMetadata, ctxt: &CommandCtxt<'_>) -> Result<(), ExecutionError> {
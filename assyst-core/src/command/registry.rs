@@ -24,14 +24,23 @@ declare_commands!(
     fun::translation::translate_command,
     image::ahshit_command,
     image::aprilfools_command,
+    image::assemble::assemble_command,
+    image::audio::bassboost_command,
     image::audio::drip_command,
+    image::audio::extractaudio_command,
     image::audio::femurbreaker_command,
+    image::audio::muteaudio_command,
+    image::audio::nightcore_command,
+    image::audio::pitch_command,
+    image::audio::reverb_command,
     image::audio::siren_command,
     image::audio::sweden_command,
     image::audio::terraria_command,
+    image::audio::volume_command,
     image::bloom::bloom_command,
     image::blur_command,
     image::caption::caption_command,
+    image::chain::chain_command,
     image::deepfry_command,
     image::fisheye_command,
     image::flip_command,
@@ -60,6 +69,11 @@ declare_commands!(
     image::makesweet::valentine_command,
     image::meme_command,
     image::motivate_command,
+    image::multi::collage_command,
+    image::multi::compare_command,
+    image::multi::grid_command,
+    image::multi::hstack_command,
+    image::multi::vstack_command,
     image::neon_command,
     image::overlay_command,
     image::paint_command,
@@ -78,6 +92,9 @@ declare_commands!(
     image::spread_command,
     image::swirl_command,
     image::uncaption_command,
+    image::video::concat_command,
+    image::video::cut_command,
+    image::video::trim_command,
     image::wormhole_command,
     image::zoom_command,
     image::zoomblur_command,
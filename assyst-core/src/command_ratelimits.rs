@@ -1,5 +1,7 @@
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
+use assyst_tag::errors::BudgetScope;
 use moka::sync::Cache;
 
 /// All command ratelimits, in the format <(guild/user id, command name) => time command was
@@ -23,3 +25,101 @@ impl CommandRatelimits {
         self.0.get(&(id, command_name))
     }
 }
+
+/// Window over which tag execution time is accumulated
+pub const TAG_BUDGET_WINDOW: Duration = Duration::from_secs(60);
+/// Execution time a single user may spend running tags per window
+pub const USER_TAG_BUDGET: Duration = Duration::from_secs(30);
+/// Execution time a single tag may spend running per window, across all users
+pub const TAG_BUDGET: Duration = Duration::from_secs(45);
+
+/// Time spent running tags, in the format <key => (start of window, time spent in window)>.
+/// Users are keyed by their ID, and tags by (guild ID or personal tag owner ID, tag name).
+///
+/// Time is reserved before a tag runs and the unused part refunded afterwards, so that tags running
+/// at the same time can't each spend the full budget.
+pub struct TagExecutionBudgets {
+    users: Cache<u64, (Instant, Duration)>,
+    tags: Cache<(u64, String), (Instant, Duration)>,
+    /// Held while budgets are updated, as each update reads and then writes both caches
+    lock: Mutex<()>,
+}
+impl TagExecutionBudgets {
+    pub fn new() -> Self {
+        Self {
+            users: Cache::builder()
+                .max_capacity(1000)
+                .time_to_live(TAG_BUDGET_WINDOW)
+                .build(),
+            tags: Cache::builder()
+                .max_capacity(1000)
+                .time_to_live(TAG_BUDGET_WINDOW)
+                .build(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Reserves up to `max` execution time from both the user's and the tag's budget, returning how
+    /// much was reserved, or which budget has been used up along with the time until it resets.
+    ///
+    /// Time that ends up unused should be given back with [`Self::refund`].
+    pub fn reserve(
+        &self,
+        user_id: u64,
+        tag: &(u64, String),
+        max: Duration,
+    ) -> Result<Duration, (BudgetScope, Duration)> {
+        let _guard = self.lock.lock().unwrap();
+
+        let user = Self::remaining_in(self.users.get(&user_id), USER_TAG_BUDGET).map_err(|r| (BudgetScope::User, r))?;
+        let tag_remaining = Self::remaining_in(self.tags.get(tag), TAG_BUDGET).map_err(|r| (BudgetScope::Tag, r))?;
+        let reserved = user.min(tag_remaining).min(max);
+
+        self.users
+            .insert(user_id, Self::accumulate(self.users.get(&user_id), reserved));
+        self.tags
+            .insert(tag.clone(), Self::accumulate(self.tags.get(tag), reserved));
+
+        Ok(reserved)
+    }
+
+    /// Gives back reserved execution time that a tag did not use.
+    pub fn refund(&self, user_id: u64, tag: &(u64, String), unused: Duration) {
+        let _guard = self.lock.lock().unwrap();
+
+        if let Some(entry) = Self::deduct(self.users.get(&user_id), unused) {
+            self.users.insert(user_id, entry);
+        }
+        if let Some(entry) = Self::deduct(self.tags.get(tag), unused) {
+            self.tags.insert(tag.clone(), entry);
+        }
+    }
+
+    fn remaining_in(entry: Option<(Instant, Duration)>, budget: Duration) -> Result<Duration, Duration> {
+        match entry {
+            Some((start, spent)) if start.elapsed() < TAG_BUDGET_WINDOW => {
+                if spent >= budget {
+                    Err(TAG_BUDGET_WINDOW - start.elapsed())
+                } else {
+                    Ok(budget - spent)
+                }
+            },
+            _ => Ok(budget),
+        }
+    }
+
+    fn accumulate(entry: Option<(Instant, Duration)>, elapsed: Duration) -> (Instant, Duration) {
+        match entry {
+            Some((start, spent)) if start.elapsed() < TAG_BUDGET_WINDOW => (start, spent + elapsed),
+            _ => (Instant::now(), elapsed),
+        }
+    }
+
+    /// Takes `unused` off of the time spent in a window, unless the window has already ended.
+    fn deduct(entry: Option<(Instant, Duration)>, unused: Duration) -> Option<(Instant, Duration)> {
+        match entry {
+            Some((start, spent)) if start.elapsed() < TAG_BUDGET_WINDOW => Some((start, spent.saturating_sub(unused))),
+            _ => None,
+        }
+    }
+}
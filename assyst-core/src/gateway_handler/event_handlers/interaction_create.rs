@@ -198,7 +198,11 @@ pub async fn handle(assyst: ThreadSafeAssyst, InteractionCreate(interaction): In
                 let _ = ctxt.cx.reply(format!(":warning: ``{e}``")).await;
             }*/
 
-            if let Err(err) = command.execute_interaction_command(ctxt.clone()).await {
+            if let Err(err) = ctxt
+                .cx
+                .with_queue_updates(command.execute_interaction_command(ctxt.clone()))
+                .await
+            {
                 match err.get_severity() {
                     ErrorSeverity::Low => {
                         if let ExecutionError::MetadataCheck(e) = err {
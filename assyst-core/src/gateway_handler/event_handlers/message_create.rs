@@ -51,7 +51,11 @@ pub async fn handle(assyst: ThreadSafeAssyst, MessageCreate(message): MessageCre
             };
             let ctxt = RawMessageParseCtxt::new(CommandCtxt::new(&data), result.args);
 
-            if let Err(err) = result.command.execute_raw_message(ctxt.clone()).await {
+            if let Err(err) = ctxt
+                .cx
+                .with_queue_updates(result.command.execute_raw_message(ctxt.clone()))
+                .await
+            {
                 match err.get_severity() {
                     ErrorSeverity::Low => debug!("{err:?}"),
                     ErrorSeverity::High => match err {
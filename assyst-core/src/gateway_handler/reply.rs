@@ -3,6 +3,7 @@ use std::time::Instant;
 
 use assyst_common::util::filetype::{Type, get_sig};
 use tokio::sync::Mutex;
+use tracing::debug;
 use twilight_model::channel::message::component::ActionRow;
 use twilight_model::channel::message::{AllowedMentions, Component};
 use twilight_model::http::attachment::Attachment as TwilightAttachment;
@@ -11,7 +12,9 @@ use twilight_model::id::Id;
 use twilight_util::builder::InteractionResponseDataBuilder;
 
 use crate::command::CommandCtxt;
-use crate::command::messagebuilder::MessageBuilder;
+use crate::command::arguments::Image;
+use crate::command::componentctxt::ComponentCtxtRegister;
+use crate::command::messagebuilder::{Attachment, MessageBuilder};
 use crate::replies::{Reply, ReplyInUse, ReplyState};
 use crate::rest::NORMAL_DISCORD_UPLOAD_LIMIT_BYTES;
 use crate::rest::filer::upload_to_filer;
@@ -34,42 +37,109 @@ fn trim_content_fits(content: &mut String) {
     }
 }
 
-/// Gets the Filer URL for this attachment if it exceeds the guild's upload limit.
-async fn get_filer_url(
+/// Lays out components in action rows.\
+/// If the components are not already action rows, they are all placed in a single row.
+fn component_rows(components: Vec<Component>) -> Vec<Component> {
+    if components.iter().all(|c| matches!(c, Component::ActionRow(_))) {
+        components
+    } else {
+        vec![Component::ActionRow(ActionRow { id: None, components })]
+    }
+}
+
+/// Registers a component context under all of its custom IDs.
+fn register_component_ctxt(ctxt: &CommandCtxt<'_>, register: ComponentCtxtRegister) {
+    let wrapped = Arc::new(Mutex::new(register.1));
+    for cid in register.0 {
+        ctxt.data.assyst.component_contexts.insert(&cid, &wrapped);
+    }
+}
+
+/// How an attachment is sent, once it fits in the guild's upload limit.
+enum Upload {
+    /// As an attachment, with a note of what was downgraded to make it fit, if anything
+    Attachment(Attachment, Option<String>),
+    /// As a Filer URL, after the content of the message
+    Filer(String),
+}
+
+/// Makes sure that an attachment fits in the guild's upload limit. Images and videos over the
+/// limit are first shrunk by Flux, and only uploaded to Filer if they still don't fit. Anything
+/// else over the limit goes straight to Filer.
+async fn fit_upload_limit(
     ctxt: &CommandCtxt<'_>,
     content: Option<&String>,
-    data: Vec<u8>,
-) -> anyhow::Result<Option<String>> {
-    let filer_url;
-    let filer_formatted;
-
-    if data.len() > NORMAL_DISCORD_UPLOAD_LIMIT_BYTES as usize {
-        let guild_upload_limit = if let Some(guild_id) = ctxt.data.guild_id {
-            ctxt.assyst()
-                .rest_cache_handler
-                .get_guild_upload_limit_bytes(guild_id.get())
-                .await?
-        } else {
-            NORMAL_DISCORD_UPLOAD_LIMIT_BYTES
-        };
-
-        if data.len() > guild_upload_limit as usize {
-            filer_url = upload_to_filer(
-                &ctxt.assyst().reqwest_client,
-                data.clone(),
-                get_sig(&data).unwrap_or(Type::PNG).as_mime(),
-            )
-            .await?;
+    attachment: Attachment,
+) -> anyhow::Result<Upload> {
+    if attachment.data.len() <= NORMAL_DISCORD_UPLOAD_LIMIT_BYTES as usize {
+        return Ok(Upload::Attachment(attachment, None));
+    }
 
-            if let Some(content) = content {
-                filer_formatted = format!("{content} {filer_url}");
-                return Ok(Some(filer_formatted));
-            }
-            return Ok(Some(filer_url));
+    let guild_upload_limit = if let Some(guild_id) = ctxt.data.guild_id {
+        ctxt.assyst()
+            .rest_cache_handler
+            .get_guild_upload_limit_bytes(guild_id.get())
+            .await?
+    } else {
+        NORMAL_DISCORD_UPLOAD_LIMIT_BYTES
+    };
+
+    if attachment.data.len() <= guild_upload_limit as usize {
+        return Ok(Upload::Attachment(attachment, None));
+    }
+
+    let is_media = get_sig(&attachment.data).is_some_and(|t| !matches!(t, Type::MP3 | Type::ZIP));
+    if is_media {
+        match ctxt
+            .flux_handler()
+            .shrink(
+                attachment.data.clone(),
+                guild_upload_limit,
+                ctxt.data.author.id.get(),
+                ctxt.data.guild_id.map(Id::get),
+            )
+            .await
+        {
+            Ok(shrunk) if shrunk.output.len() <= guild_upload_limit as usize => {
+                let note = format!(
+                    "The output was too large to upload, so it was shrunk ({}).",
+                    shrunk
+                        .steps
+                        .iter()
+                        .map(|step| step.description())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                return Ok(Upload::Attachment(Image(shrunk.output).into(), Some(note)));
+            },
+            Ok(_) => {},
+            Err(e) => debug!("Failed to shrink output, uploading to Filer instead: {e:#}"),
         }
-        return Ok(None);
     }
-    Ok(None)
+
+    // the original is uploaded, since Filer has no need for the shrunk one
+    let data = attachment.data;
+    let filer_url = upload_to_filer(
+        &ctxt.assyst().reqwest_client,
+        data.clone(),
+        get_sig(&data).unwrap_or(Type::PNG).as_mime(),
+    )
+    .await?;
+
+    Ok(Upload::Filer(match content {
+        Some(content) => format!("{content} {filer_url}"),
+        None => filer_url,
+    }))
+}
+
+/// Content of a message with `note` added after it.
+fn content_with_note(content: Option<&String>, note: &str) -> String {
+    let mut content = match content {
+        Some(content) => format!("{content}\n{note}"),
+        None => note.to_owned(),
+    };
+    trim_content_fits(&mut content);
+    content
 }
 
 pub async fn edit(ctxt: &CommandCtxt<'_>, builder: MessageBuilder, reply: ReplyInUse) -> anyhow::Result<()> {
@@ -93,24 +163,42 @@ pub async fn edit(ctxt: &CommandCtxt<'_>, builder: MessageBuilder, reply: ReplyI
 
     let attachments;
     let url;
+    let noted;
     if let Some(attachment) = builder.attachment {
-        if let Some(found_url) = get_filer_url(ctxt, builder.content.as_ref(), attachment.data.clone()).await? {
-            url = found_url;
-            message = message.content(Some(&url));
-        } else {
-            attachments = [TwilightAttachment::from_bytes(
-                attachment.name.into(),
-                attachment.data,
-                0,
-            )];
-            message = message.attachments(&attachments);
-            if builder.content.is_none() {
-                message = message.content(Some(""));
-            }
-        };
+        match fit_upload_limit(ctxt, builder.content.as_ref(), attachment).await? {
+            Upload::Filer(found_url) => {
+                url = found_url;
+                message = message.content(Some(&url));
+            },
+            Upload::Attachment(attachment, note) => {
+                attachments = [TwilightAttachment::from_bytes(
+                    attachment.name.into(),
+                    attachment.data,
+                    0,
+                )];
+                message = message.attachments(&attachments);
+                if let Some(note) = note {
+                    noted = content_with_note(builder.content.as_ref(), &note);
+                    message = message.content(Some(&noted));
+                } else if builder.content.is_none() {
+                    message = message.content(Some(""));
+                }
+            },
+        }
+    }
+
+    let cs;
+    if let Some(components) = builder.components {
+        cs = component_rows(components);
+        message = message.components(Some(&cs));
     }
 
     message.await?;
+
+    if let Some(cx) = builder.component_ctxt {
+        register_component_ctxt(ctxt, cx);
+    }
+
     Ok(())
 }
 
@@ -147,26 +235,33 @@ async fn create_message(ctxt: &CommandCtxt<'_>, builder: MessageBuilder) -> anyh
 
     let attachments;
     let url;
+    let noted;
     if let Some(attachment) = builder.attachment {
-        if let Some(found_url) = get_filer_url(ctxt, builder.content.as_ref(), attachment.data.clone()).await? {
-            url = found_url;
-            message = message.content(&url);
-        } else {
-            attachments = [TwilightAttachment::from_bytes(
-                attachment.name.into(),
-                attachment.data,
-                0,
-            )];
-            message = message.attachments(&attachments);
-            if builder.content.is_none() {
-                message = message.content("");
-            }
-        };
+        match fit_upload_limit(ctxt, builder.content.as_ref(), attachment).await? {
+            Upload::Filer(found_url) => {
+                url = found_url;
+                message = message.content(&url);
+            },
+            Upload::Attachment(attachment, note) => {
+                attachments = [TwilightAttachment::from_bytes(
+                    attachment.name.into(),
+                    attachment.data,
+                    0,
+                )];
+                message = message.attachments(&attachments);
+                if let Some(note) = note {
+                    noted = content_with_note(builder.content.as_ref(), &note);
+                    message = message.content(&noted);
+                } else if builder.content.is_none() {
+                    message = message.content("");
+                }
+            },
+        }
     }
 
     let cs;
     if let Some(components) = builder.components {
-        cs = vec![Component::ActionRow(ActionRow { id: None, components })];
+        cs = component_rows(components);
         message = message.components(&cs);
     }
 
@@ -184,10 +279,7 @@ async fn create_message(ctxt: &CommandCtxt<'_>, builder: MessageBuilder) -> anyh
     );
 
     if let Some(cx) = builder.component_ctxt {
-        let wrapped = Arc::new(Mutex::new(cx.1.clone()));
-        for cid in cx.0 {
-            ctxt.data.assyst.component_contexts.insert(&cid, &wrapped);
-        }
+        register_component_ctxt(ctxt, cx);
     }
 
     Ok(())
@@ -216,9 +308,23 @@ pub async fn reply_interaction_command(ctxt: &CommandCtxt<'_>, builder: MessageB
         .get_interaction_command(ctxt.data.interaction_id.unwrap().get())
         .is_some();
 
+    let mut content = builder.content.clone();
+    let mut attachment = None;
+    if let Some(a) = builder.attachment {
+        match fit_upload_limit(ctxt, builder.content.as_ref(), a).await? {
+            Upload::Filer(url) => content = Some(url),
+            Upload::Attachment(a, note) => {
+                if let Some(note) = note {
+                    content = Some(content_with_note(builder.content.as_ref(), &note));
+                }
+                attachment = Some(a);
+            },
+        }
+    }
+
     let c = ctxt.assyst().interaction_client();
     let mut response_data = InteractionResponseDataBuilder::new();
-    if let Some(ref a) = builder.attachment {
+    if let Some(ref a) = attachment {
         let attachments = [TwilightAttachment::from_bytes(a.name.clone().into(), a.data.clone(), 0)];
         response_data = response_data.attachments(attachments);
         response_data = response_data.content("");
@@ -226,10 +332,15 @@ pub async fn reply_interaction_command(ctxt: &CommandCtxt<'_>, builder: MessageB
 
     response_data = response_data.allowed_mentions(AllowedMentions::default());
 
-    if let Some(c) = builder.content.clone() {
+    if let Some(c) = content.clone() {
         response_data = response_data.content(c);
     }
 
+    let components = builder.components.map(component_rows);
+    if let Some(ref components) = components {
+        response_data = response_data.components(components.clone());
+    }
+
     let response = InteractionResponse {
         kind: twilight_model::http::interaction::InteractionResponseType::ChannelMessageWithSource,
         data: Some(response_data.build()),
@@ -240,16 +351,16 @@ pub async fn reply_interaction_command(ctxt: &CommandCtxt<'_>, builder: MessageB
         let mut update = c.update_response(&token);
         let attachments;
 
-        if let Some(ref a) = builder.attachment {
+        if let Some(ref a) = attachment {
             attachments = [TwilightAttachment::from_bytes(a.name.clone().into(), a.data.clone(), 0)];
             update = update.attachments(&attachments);
         }
 
-        if let Some(ref c) = builder.content {
+        if let Some(ref c) = content {
             update = update.content(Some(c));
         }
 
-        if let Some(ref components) = builder.components {
+        if let Some(ref components) = components {
             update = update.components(Some(components));
         }
 
@@ -267,5 +378,9 @@ pub async fn reply_interaction_command(ctxt: &CommandCtxt<'_>, builder: MessageB
             .insert_interaction_command(ctxt.data.interaction_id.unwrap().get());
     }
 
+    if let Some(cx) = builder.component_ctxt {
+        register_component_ctxt(ctxt, cx);
+    }
+
     Ok(())
 }
@@ -17,6 +17,7 @@ use assyst_common::pipe::{GATEWAY_PIPE_PATH, Pipe};
 use assyst_common::util::tracing_init;
 use assyst_common::{err, ok_or_break};
 use assyst_flux_iface::FluxHandler;
+use assyst_flux_iface::workspace::JobWorkspace;
 use command::registry::register_interaction_commands;
 use gateway_handler::handle_raw_event;
 use gateway_handler::incoming_event::IncomingEvent;
@@ -170,6 +171,12 @@ async fn main() {
         info!("BadTranslator channels disabled in config.dev.disable_bad_translator_channels, skipping init");
     }
 
+    match JobWorkspace::remove_stale().await {
+        Ok(0) => {},
+        Ok(removed) => info!("Removed {removed} stale Flux workspace(s)"),
+        Err(e) => err!("Failed to remove stale Flux workspaces: {e:?}"),
+    }
+
     let a = assyst.clone();
     spawn(async move {
         info!("Compiling Flux...");
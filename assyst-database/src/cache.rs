@@ -8,6 +8,7 @@ use moka::sync::Cache;
 
 use crate::model::colour_role::ColourRole;
 use crate::model::prefix::Prefix;
+use crate::model::public_tag::PublicTag;
 
 trait TCacheV = Send + Sync + Clone + 'static;
 trait TCacheK = Hash + Send + Sync + Eq + Clone + 'static;
@@ -33,6 +34,7 @@ pub struct DatabaseCache {
     disabled_commands: Cache<u64, Arc<Mutex<HashSet<String>>>>,
     copied_tags: Cache<u64 /* user id */, String /* content */>,
     guild_tag_names: Cache<u64, Vec<(u64 /* author id */, String)>>,
+    public_tags: Cache<i64 /* public tag id */, PublicTag>,
     guild_colour_roles: Cache<u64, Vec<ColourRole>>,
 }
 impl DatabaseCache {
@@ -43,6 +45,7 @@ impl DatabaseCache {
             disabled_commands: default_cache(),
             copied_tags: default_cache_sized(u64::MAX),
             guild_tag_names: default_cache(),
+            public_tags: default_cache(),
             guild_colour_roles: default_cache(),
         }
     }
@@ -153,6 +156,22 @@ impl DatabaseCache {
         self.guild_tag_names.get(&guild_id)
     }
 
+    pub fn remove_guild_tag_names(&self, guild_id: u64) {
+        self.guild_tag_names.invalidate(&guild_id);
+    }
+
+    pub fn insert_public_tag(&self, tag: PublicTag) {
+        self.public_tags.insert(tag.id, tag);
+    }
+
+    pub fn get_public_tag(&self, id: i64) -> Option<PublicTag> {
+        self.public_tags.get(&id)
+    }
+
+    pub fn remove_public_tag(&self, id: i64) {
+        self.public_tags.invalidate(&id);
+    }
+
     pub fn insert_guild_colour_roles(&self, guild_id: u64, roles: Vec<ColourRole>) {
         self.guild_colour_roles.insert(guild_id, roles);
     }
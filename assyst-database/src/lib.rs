@@ -44,6 +44,18 @@ impl DatabaseHandler {
         Ok(Self { pool, cache })
     }
 
+    /// Creates a handler that only connects to the database once it is first used. Useful for
+    /// tests of code that needs a handler, but never touches the database.
+    #[cfg(feature = "test-util")]
+    pub fn new_lazy(url: &str) -> anyhow::Result<Self> {
+        let pool = PgPoolOptions::new().max_connections(MAX_CONNECTIONS).connect_lazy(url)?;
+
+        Ok(Self {
+            pool,
+            cache: DatabaseCache::new(),
+        })
+    }
+
     pub async fn database_size(&self) -> anyhow::Result<DatabaseSize> {
         let query = r"SELECT pg_size_pretty(pg_database_size('assyst')) as size";
 
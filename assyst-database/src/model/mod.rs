@@ -7,6 +7,15 @@ pub mod free_tier_2_requests;
 pub mod global_blacklist;
 pub mod guild_disabled_command;
 pub mod prefix;
+pub mod public_tag;
 pub mod reminder;
 pub mod tag;
+pub mod tag_alias;
+pub mod tag_collaborator;
+pub mod tag_install;
+pub mod tag_lock;
+pub mod tag_report;
+pub mod tag_revision;
+pub mod tag_usage;
+pub mod user_tag;
 pub mod user_votes;
@@ -0,0 +1,102 @@
+use crate::DatabaseHandler;
+
+/// A tag published to the public library, which can be installed into any guild.
+///
+/// Table schema:
+/// ```sql
+/// CREATE TABLE public_tags (
+///     id BIGSERIAL PRIMARY KEY,
+///     name TEXT NOT NULL,
+///     description TEXT NOT NULL,
+///     data TEXT NOT NULL,
+///     author BIGINT NOT NULL,
+///     version BIGINT NOT NULL,
+///     published_at BIGINT NOT NULL,
+///     updated_at BIGINT NOT NULL,
+///     UNIQUE (author, name)
+/// );
+/// ```
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct PublicTag {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub data: String,
+    pub author: i64,
+    /// Incremented every time the author republishes the tag
+    pub version: i64,
+    pub published_at: i64,
+    pub updated_at: i64,
+}
+impl PublicTag {
+    pub async fn get(handler: &DatabaseHandler, id: i64) -> Result<Option<Self>, sqlx::Error> {
+        if let Some(tag) = handler.cache.get_public_tag(id) {
+            return Ok(Some(tag));
+        }
+
+        let query = r"SELECT * FROM public_tags WHERE id = $1";
+
+        let result: Option<Self> = sqlx::query_as(query).bind(id).fetch_optional(&handler.pool).await?;
+
+        if let Some(ref tag) = result {
+            handler.cache.insert_public_tag(tag.clone());
+        }
+
+        Ok(result)
+    }
+
+    /// Publishes a tag, or publishes a new version of it if the author has already published a
+    /// tag with the same name.
+    pub async fn publish(
+        handler: &DatabaseHandler,
+        name: &str,
+        description: &str,
+        data: &str,
+        author: i64,
+        timestamp: i64,
+    ) -> Result<Self, sqlx::Error> {
+        let query = r"INSERT INTO public_tags (name, description, data, author, version, published_at, updated_at) VALUES ($1, $2, $3, $4, 1, $5, $5)
+            ON CONFLICT (author, name) DO UPDATE SET description = $2, data = $3, version = public_tags.version + 1, updated_at = $5
+            RETURNING *";
+
+        let tag: Self = sqlx::query_as(query)
+            .bind(name)
+            .bind(description)
+            .bind(data)
+            .bind(author)
+            .bind(timestamp)
+            .fetch_one(&handler.pool)
+            .await?;
+
+        handler.cache.insert_public_tag(tag.clone());
+
+        Ok(tag)
+    }
+
+    pub async fn delete(handler: &DatabaseHandler, id: i64, author: i64) -> Result<bool, sqlx::Error> {
+        let query = r"DELETE FROM public_tags WHERE id = $1 AND author = $2";
+
+        let deleted = sqlx::query(query)
+            .bind(id)
+            .bind(author)
+            .execute(&handler.pool)
+            .await
+            .map(|r| r.rows_affected() > 0)?;
+
+        if deleted {
+            handler.cache.remove_public_tag(id);
+        }
+
+        Ok(deleted)
+    }
+
+    pub async fn search(handler: &DatabaseHandler, search: &str, limit: i64) -> Result<Vec<Self>, sqlx::Error> {
+        let query = r"SELECT * FROM public_tags WHERE position($1 in name)>0 OR position($1 in lower(description))>0 ORDER BY updated_at DESC LIMIT $2";
+
+        sqlx::query_as(query)
+            .bind(search)
+            .bind(limit)
+            .fetch_all(&handler.pool)
+            .await
+    }
+}
@@ -1,5 +1,23 @@
+use sqlx::{Postgres, Transaction};
+
+use super::tag_alias::TagAlias;
+use super::tag_collaborator::TagCollaborator;
+use super::tag_install::TagInstall;
+use super::tag_report::TagReport;
+use super::tag_revision::{TagRevision, TagRevisionKind};
+use super::tag_usage::TagUsage;
 use crate::{is_unique_violation, Count, DatabaseHandler};
 
+/// A tag in a guild.
+///
+/// Tag searches use a weighted full-text search column, with names weighted above contents. It is
+/// added to existing databases with the following one-off migration:
+/// ```sql
+/// ALTER TABLE tags ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+///     setweight(to_tsvector('simple', name), 'A') || setweight(to_tsvector('simple', data), 'B')
+/// ) STORED;
+/// CREATE INDEX CONCURRENTLY tags_search_vector_idx ON tags USING GIN (search_vector);
+/// ```
 #[derive(sqlx::FromRow, Debug, Clone)]
 pub struct Tag {
     pub name: String,
@@ -9,8 +27,9 @@ pub struct Tag {
     pub created_at: i64,
 }
 impl Tag {
+    /// Fetches a tag by its name, or by any of its aliases.
     pub async fn get(handler: &DatabaseHandler, guild_id: i64, name: &str) -> anyhow::Result<Option<Self>> {
-        let query = r"SELECT * FROM tags WHERE name = $1 AND guild_id = $2";
+        let query = r"SELECT * FROM tags WHERE guild_id = $2 AND name = COALESCE((SELECT target FROM tag_aliases WHERE guild_id = $2 AND alias = $1), $1)";
 
         let result = sqlx::query_as(query)
             .bind(name)
@@ -25,51 +44,118 @@ impl Tag {
         }
     }
 
+    /// Creates the tag, recording it as the first revision in the same transaction.
     pub async fn set(&self, handler: &DatabaseHandler) -> Result<bool, sqlx::Error> {
         let query = r"INSERT INTO tags VALUES ($1, $2, $3, $4, $5)";
 
-        sqlx::query(query)
+        let mut tx = handler.pool.begin().await?;
+
+        let created = sqlx::query(query)
             .bind(&self.name)
             .bind(&self.data)
             .bind(self.author)
             .bind(self.guild_id)
             .bind(self.created_at)
-            .execute(&handler.pool)
+            .execute(&mut *tx)
             .await
             .map(|_| true)
-            .or_else(|e| if is_unique_violation(&e) { Ok(false) } else { Err(e) })
+            .or_else(|e| if is_unique_violation(&e) { Ok(false) } else { Err(e) })?;
+
+        if created {
+            TagRevision::record(
+                &mut tx,
+                self.guild_id,
+                &self.name,
+                TagRevisionKind::Create,
+                &self.data,
+                self.author,
+            )
+            .await?;
+            tx.commit().await?;
+            handler.cache.remove_guild_tag_names(self.guild_id as u64);
+        }
+
+        Ok(created)
     }
 
-    pub async fn delete_force(handler: &DatabaseHandler, name: &str, guild_id: i64) -> Result<bool, sqlx::Error> {
-        let query = r"DELETE FROM tags WHERE name = $1 AND guild_id = $2";
+    /// Deletes a tag regardless of who owns it, recording the deletion as a revision made by
+    /// `deleted_by`.
+    pub async fn delete_force(
+        handler: &DatabaseHandler,
+        name: &str,
+        guild_id: i64,
+        deleted_by: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let query = r"DELETE FROM tags WHERE name = $1 AND guild_id = $2 RETURNING data";
+
+        let mut tx = handler.pool.begin().await?;
 
-        sqlx::query(query)
+        let deleted: Option<(String,)> = sqlx::query_as(query)
             .bind(name)
             .bind(guild_id)
-            .execute(&handler.pool)
-            .await
-            .map(|rows| rows.rows_affected() > 0)
-            .or_else(|e| if is_unique_violation(&e) { Ok(false) } else { Err(e) })
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        Self::finish_delete(handler, tx, name, guild_id, deleted, deleted_by).await
     }
 
+    /// Deletes a tag owned by `author`, recording the deletion as a revision.
     pub async fn delete(
         handler: &DatabaseHandler,
         name: &str,
         guild_id: i64,
         author: i64,
     ) -> Result<bool, sqlx::Error> {
-        let query = r"DELETE FROM tags WHERE name = $1 AND author = $2 AND guild_id = $3";
+        let query = r"DELETE FROM tags WHERE name = $1 AND author = $2 AND guild_id = $3 RETURNING data";
 
-        sqlx::query(query)
+        let mut tx = handler.pool.begin().await?;
+
+        let deleted: Option<(String,)> = sqlx::query_as(query)
             .bind(name)
             .bind(author)
             .bind(guild_id)
-            .execute(&handler.pool)
-            .await
-            .map(|rows| rows.rows_affected() > 0)
-            .or_else(|e| if is_unique_violation(&e) { Ok(false) } else { Err(e) })
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        Self::finish_delete(handler, tx, name, guild_id, deleted, author).await
+    }
+
+    /// Records the revision of a deleted tag and commits the deletion, then removes everything
+    /// associated with the tag. The associated rows are removed outside of the transaction, as
+    /// they go through the pool.
+    async fn finish_delete(
+        handler: &DatabaseHandler,
+        mut tx: Transaction<'_, Postgres>,
+        name: &str,
+        guild_id: i64,
+        deleted: Option<(String,)>,
+        deleted_by: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let Some((data,)) = deleted else {
+            return Ok(false);
+        };
+
+        TagRevision::record(&mut tx, guild_id, name, TagRevisionKind::Delete, &data, deleted_by).await?;
+        tx.commit().await?;
+        handler.cache.remove_guild_tag_names(guild_id as u64);
+
+        Self::delete_associated(handler, name, guild_id).await?;
+
+        Ok(true)
+    }
+
+    /// Removes the collaborators, aliases, install record, usage statistics and reports of a deleted
+    /// tag. Its lock is kept, so that the tag can't be recreated without it.
+    async fn delete_associated(handler: &DatabaseHandler, name: &str, guild_id: i64) -> Result<(), sqlx::Error> {
+        TagCollaborator::delete_for_tag(handler, guild_id, name).await?;
+        TagAlias::delete_for_target(handler, guild_id, name).await?;
+        TagInstall::delete_for_tag(handler, guild_id, name).await?;
+        TagUsage::delete_for_tag(handler, guild_id, name).await?;
+        TagReport::delete_for_tag(handler, guild_id, name).await
     }
 
+    /// Edits a tag, provided that `author` owns it or is one of its collaborators and it isn't locked.
+    /// The edit is recorded as a revision in the same transaction.
     pub async fn edit(
         handler: &DatabaseHandler,
         author: i64,
@@ -77,16 +163,51 @@ impl Tag {
         name: &str,
         new_content: &str,
     ) -> Result<bool, sqlx::Error> {
-        let query = r"UPDATE tags SET data = $1 WHERE name = $2 AND author = $3 AND guild_id = $4";
+        let query = r"UPDATE tags SET data = $1 WHERE name = $2 AND guild_id = $4 AND (author = $3 OR EXISTS (SELECT 1 FROM tag_collaborators c WHERE c.guild_id = tags.guild_id AND c.name = tags.name AND c.user_id = $3)) AND NOT EXISTS (SELECT 1 FROM tag_locks l WHERE l.guild_id = tags.guild_id AND l.name = tags.name)";
+
+        let mut tx = handler.pool.begin().await?;
 
-        sqlx::query(query)
+        let edited = sqlx::query(query)
             .bind(new_content)
             .bind(name)
             .bind(author)
             .bind(guild_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+            > 0;
+
+        if edited {
+            TagRevision::record(&mut tx, guild_id, name, TagRevisionKind::Edit, new_content, author).await?;
+            tx.commit().await?;
+        }
+
+        Ok(edited)
+    }
+
+    pub async fn transfer(
+        handler: &DatabaseHandler,
+        guild_id: i64,
+        name: &str,
+        new_author: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let query = r"UPDATE tags SET author = $1 WHERE name = $2 AND guild_id = $3";
+
+        let transferred = sqlx::query(query)
+            .bind(new_author)
+            .bind(name)
+            .bind(guild_id)
             .execute(&handler.pool)
-            .await
-            .map(|r| r.rows_affected() > 0)
+            .await?
+            .rows_affected()
+            > 0;
+
+        if transferred {
+            // the cached names are paired with their author
+            handler.cache.remove_guild_tag_names(guild_id as u64);
+        }
+
+        Ok(transferred)
     }
 
     pub async fn get_paged(
@@ -179,11 +300,19 @@ impl Tag {
         result.map(|c| c.count)
     }
 
-    pub async fn search_in_guild(handler: &DatabaseHandler, guild_id: i64, search: &str) -> anyhow::Result<Vec<Self>> {
-        let query = r"SELECT * FROM tags WHERE guild_id = $1 AND position($2 in name)>0 ORDER BY created_at DESC";
+    /// Searches the names and contents of tags in a guild, best matches first.
+    pub async fn search_in_guild(
+        handler: &DatabaseHandler,
+        guild_id: i64,
+        search: &str,
+    ) -> anyhow::Result<Vec<TagSearchResult>> {
+        let query = r"SELECT *, ts_headline('simple', data, to_tsquery('simple', $2), 'StartSel=**, StopSel=**, MinWords=5, MaxWords=15, MaxFragments=1') AS snippet FROM tags
+            WHERE guild_id = $1 AND (search_vector @@ to_tsquery('simple', $2) OR position($3 in name) > 0)
+            ORDER BY (name = $3) DESC, ts_rank(search_vector, to_tsquery('simple', $2)) + (position($3 in name) > 0)::INT DESC, created_at DESC";
 
         let result = sqlx::query_as(query)
             .bind(guild_id)
+            .bind(search_tsquery(search))
             .bind(search)
             .fetch_all(&handler.pool)
             .await?;
@@ -191,17 +320,21 @@ impl Tag {
         Ok(result)
     }
 
+    /// Searches the names and contents of tags in a guild owned by `author`, best matches first.
     pub async fn search_in_guild_for_user(
         handler: &DatabaseHandler,
         guild_id: i64,
         author: i64,
         search: &str,
-    ) -> anyhow::Result<Vec<Self>> {
-        let query = r"SELECT * FROM tags WHERE guild_id = $1 AND author = $2 AND position($3 in name)>0 ORDER BY created_at DESC";
+    ) -> anyhow::Result<Vec<TagSearchResult>> {
+        let query = r"SELECT *, ts_headline('simple', data, to_tsquery('simple', $3), 'StartSel=**, StopSel=**, MinWords=5, MaxWords=15, MaxFragments=1') AS snippet FROM tags
+            WHERE guild_id = $1 AND author = $2 AND (search_vector @@ to_tsquery('simple', $3) OR position($4 in name) > 0)
+            ORDER BY (name = $4) DESC, ts_rank(search_vector, to_tsquery('simple', $3)) + (position($4 in name) > 0)::INT DESC, created_at DESC";
 
         let result = sqlx::query_as(query)
             .bind(guild_id)
             .bind(author)
+            .bind(search_tsquery(search))
             .bind(search)
             .fetch_all(&handler.pool)
             .await?;
@@ -209,3 +342,24 @@ impl Tag {
         Ok(result)
     }
 }
+
+/// A tag matched by a search, along with an excerpt of its contents.
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct TagSearchResult {
+    #[sqlx(flatten)]
+    pub tag: Tag,
+    /// Part of the tag's contents, with matching words highlighted in bold
+    pub snippet: String,
+}
+
+/// Turns a search into a tsquery matching every word as a prefix, dropping any characters that
+/// have meaning in tsquery syntax.
+fn search_tsquery(search: &str) -> String {
+    search
+        .split_whitespace()
+        .map(|word| word.chars().filter(|c| c.is_alphanumeric()).collect::<String>())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("{word}:*"))
+        .collect::<Vec<_>>()
+        .join(" & ")
+}
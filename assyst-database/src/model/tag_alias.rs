@@ -0,0 +1,72 @@
+use crate::{is_unique_violation, DatabaseHandler};
+
+/// An alternative name that resolves to another tag in the same guild.
+///
+/// Table schema:
+/// ```sql
+/// CREATE TABLE tag_aliases (
+///     guild_id BIGINT NOT NULL,
+///     alias TEXT NOT NULL,
+///     target TEXT NOT NULL,
+///     author BIGINT NOT NULL,
+///     created_at BIGINT NOT NULL,
+///     PRIMARY KEY (guild_id, alias)
+/// );
+/// ```
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct TagAlias {
+    pub guild_id: i64,
+    pub alias: String,
+    pub target: String,
+    pub author: i64,
+    pub created_at: i64,
+}
+impl TagAlias {
+    pub async fn get(handler: &DatabaseHandler, guild_id: i64, alias: &str) -> Result<Option<Self>, sqlx::Error> {
+        let query = r"SELECT * FROM tag_aliases WHERE guild_id = $1 AND alias = $2";
+
+        sqlx::query_as(query)
+            .bind(guild_id)
+            .bind(alias)
+            .fetch_optional(&handler.pool)
+            .await
+    }
+
+    pub async fn set(&self, handler: &DatabaseHandler) -> Result<bool, sqlx::Error> {
+        let query = r"INSERT INTO tag_aliases VALUES ($1, $2, $3, $4, $5)";
+
+        sqlx::query(query)
+            .bind(self.guild_id)
+            .bind(&self.alias)
+            .bind(&self.target)
+            .bind(self.author)
+            .bind(self.created_at)
+            .execute(&handler.pool)
+            .await
+            .map(|_| true)
+            .or_else(|e| if is_unique_violation(&e) { Ok(false) } else { Err(e) })
+    }
+
+    pub async fn delete(handler: &DatabaseHandler, guild_id: i64, alias: &str) -> Result<bool, sqlx::Error> {
+        let query = r"DELETE FROM tag_aliases WHERE guild_id = $1 AND alias = $2";
+
+        sqlx::query(query)
+            .bind(guild_id)
+            .bind(alias)
+            .execute(&handler.pool)
+            .await
+            .map(|r| r.rows_affected() > 0)
+    }
+
+    pub async fn delete_for_target(handler: &DatabaseHandler, guild_id: i64, target: &str) -> Result<(), sqlx::Error> {
+        let query = r"DELETE FROM tag_aliases WHERE guild_id = $1 AND target = $2";
+
+        sqlx::query(query)
+            .bind(guild_id)
+            .bind(target)
+            .execute(&handler.pool)
+            .await?;
+
+        Ok(())
+    }
+}
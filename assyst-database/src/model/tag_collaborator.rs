@@ -0,0 +1,67 @@
+use crate::{is_unique_violation, DatabaseHandler};
+
+/// A user who is allowed to edit a tag that they do not own.
+///
+/// Table schema:
+/// ```sql
+/// CREATE TABLE tag_collaborators (
+///     guild_id BIGINT NOT NULL,
+///     name TEXT NOT NULL,
+///     user_id BIGINT NOT NULL,
+///     PRIMARY KEY (guild_id, name, user_id)
+/// );
+/// ```
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct TagCollaborator {
+    pub guild_id: i64,
+    pub name: String,
+    pub user_id: i64,
+}
+impl TagCollaborator {
+    pub async fn get_for_tag(handler: &DatabaseHandler, guild_id: i64, name: &str) -> Result<Vec<Self>, sqlx::Error> {
+        let query = r"SELECT * FROM tag_collaborators WHERE guild_id = $1 AND name = $2";
+
+        sqlx::query_as(query)
+            .bind(guild_id)
+            .bind(name)
+            .fetch_all(&handler.pool)
+            .await
+    }
+
+    pub async fn set(&self, handler: &DatabaseHandler) -> Result<bool, sqlx::Error> {
+        let query = r"INSERT INTO tag_collaborators VALUES ($1, $2, $3)";
+
+        sqlx::query(query)
+            .bind(self.guild_id)
+            .bind(&self.name)
+            .bind(self.user_id)
+            .execute(&handler.pool)
+            .await
+            .map(|_| true)
+            .or_else(|e| if is_unique_violation(&e) { Ok(false) } else { Err(e) })
+    }
+
+    pub async fn delete(&self, handler: &DatabaseHandler) -> Result<bool, sqlx::Error> {
+        let query = r"DELETE FROM tag_collaborators WHERE guild_id = $1 AND name = $2 AND user_id = $3";
+
+        sqlx::query(query)
+            .bind(self.guild_id)
+            .bind(&self.name)
+            .bind(self.user_id)
+            .execute(&handler.pool)
+            .await
+            .map(|r| r.rows_affected() > 0)
+    }
+
+    pub async fn delete_for_tag(handler: &DatabaseHandler, guild_id: i64, name: &str) -> Result<(), sqlx::Error> {
+        let query = r"DELETE FROM tag_collaborators WHERE guild_id = $1 AND name = $2";
+
+        sqlx::query(query)
+            .bind(guild_id)
+            .bind(name)
+            .execute(&handler.pool)
+            .await?;
+
+        Ok(())
+    }
+}
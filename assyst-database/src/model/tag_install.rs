@@ -0,0 +1,59 @@
+use crate::DatabaseHandler;
+
+/// Links a tag in a guild to the public library tag it was installed from.
+///
+/// Table schema:
+/// ```sql
+/// CREATE TABLE tag_installs (
+///     guild_id BIGINT NOT NULL,
+///     name TEXT NOT NULL,
+///     public_id BIGINT NOT NULL,
+///     version BIGINT NOT NULL,
+///     PRIMARY KEY (guild_id, name)
+/// );
+/// ```
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct TagInstall {
+    pub guild_id: i64,
+    pub name: String,
+    pub public_id: i64,
+    /// The version of the public tag that this copy was last updated to
+    pub version: i64,
+}
+impl TagInstall {
+    pub async fn get(handler: &DatabaseHandler, guild_id: i64, name: &str) -> Result<Option<Self>, sqlx::Error> {
+        let query = r"SELECT * FROM tag_installs WHERE guild_id = $1 AND name = $2";
+
+        sqlx::query_as(query)
+            .bind(guild_id)
+            .bind(name)
+            .fetch_optional(&handler.pool)
+            .await
+    }
+
+    pub async fn set(&self, handler: &DatabaseHandler) -> Result<(), sqlx::Error> {
+        let query = r"INSERT INTO tag_installs VALUES ($1, $2, $3, $4) ON CONFLICT (guild_id, name) DO UPDATE SET public_id = $3, version = $4";
+
+        sqlx::query(query)
+            .bind(self.guild_id)
+            .bind(&self.name)
+            .bind(self.public_id)
+            .bind(self.version)
+            .execute(&handler.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_for_tag(handler: &DatabaseHandler, guild_id: i64, name: &str) -> Result<(), sqlx::Error> {
+        let query = r"DELETE FROM tag_installs WHERE guild_id = $1 AND name = $2";
+
+        sqlx::query(query)
+            .bind(guild_id)
+            .bind(name)
+            .execute(&handler.pool)
+            .await?;
+
+        Ok(())
+    }
+}
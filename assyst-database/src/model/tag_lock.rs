@@ -0,0 +1,59 @@
+use crate::{is_unique_violation, DatabaseHandler};
+
+/// A tag locked by a server manager. Locked tags cannot be edited, deleted or transferred by their
+/// author or collaborators. Locks are kept when the tag is deleted.
+///
+/// Table schema:
+/// ```sql
+/// CREATE TABLE tag_locks (
+///     guild_id BIGINT NOT NULL,
+///     name TEXT NOT NULL,
+///     locked_by BIGINT NOT NULL,
+///     locked_at BIGINT NOT NULL,
+///     PRIMARY KEY (guild_id, name)
+/// );
+/// ```
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct TagLock {
+    pub guild_id: i64,
+    pub name: String,
+    pub locked_by: i64,
+    pub locked_at: i64,
+}
+impl TagLock {
+    pub async fn get(handler: &DatabaseHandler, guild_id: i64, name: &str) -> Result<Option<Self>, sqlx::Error> {
+        let query = r"SELECT * FROM tag_locks WHERE guild_id = $1 AND name = $2";
+
+        sqlx::query_as(query)
+            .bind(guild_id)
+            .bind(name)
+            .fetch_optional(&handler.pool)
+            .await
+    }
+
+    /// Returns `false` if the tag is already locked.
+    pub async fn set(&self, handler: &DatabaseHandler) -> Result<bool, sqlx::Error> {
+        let query = r"INSERT INTO tag_locks VALUES ($1, $2, $3, $4)";
+
+        sqlx::query(query)
+            .bind(self.guild_id)
+            .bind(&self.name)
+            .bind(self.locked_by)
+            .bind(self.locked_at)
+            .execute(&handler.pool)
+            .await
+            .map(|_| true)
+            .or_else(|e| if is_unique_violation(&e) { Ok(false) } else { Err(e) })
+    }
+
+    pub async fn delete(handler: &DatabaseHandler, guild_id: i64, name: &str) -> Result<bool, sqlx::Error> {
+        let query = r"DELETE FROM tag_locks WHERE guild_id = $1 AND name = $2";
+
+        sqlx::query(query)
+            .bind(guild_id)
+            .bind(name)
+            .execute(&handler.pool)
+            .await
+            .map(|r| r.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{is_unique_violation, DatabaseHandler};
+
+/// A report made against a tag, waiting to be reviewed by a server manager.
+///
+/// Table schema:
+/// ```sql
+/// CREATE TABLE tag_reports (
+///     guild_id BIGINT NOT NULL,
+///     name TEXT NOT NULL,
+///     reporter BIGINT NOT NULL,
+///     reason TEXT NOT NULL,
+///     created_at BIGINT NOT NULL,
+///     PRIMARY KEY (guild_id, name, reporter)
+/// );
+/// ```
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct TagReport {
+    pub guild_id: i64,
+    pub name: String,
+    pub reporter: i64,
+    pub reason: String,
+    pub created_at: i64,
+}
+impl TagReport {
+    /// Returns `false` if the reporter has already reported this tag.
+    pub async fn set(&self, handler: &DatabaseHandler) -> Result<bool, sqlx::Error> {
+        let query = r"INSERT INTO tag_reports VALUES ($1, $2, $3, $4, $5)";
+
+        sqlx::query(query)
+            .bind(self.guild_id)
+            .bind(&self.name)
+            .bind(self.reporter)
+            .bind(&self.reason)
+            .bind(self.created_at)
+            .execute(&handler.pool)
+            .await
+            .map(|_| true)
+            .or_else(|e| if is_unique_violation(&e) { Ok(false) } else { Err(e) })
+    }
+
+    /// Fetches every pending report in a guild, oldest first.
+    pub async fn get_for_guild(handler: &DatabaseHandler, guild_id: i64) -> Result<Vec<Self>, sqlx::Error> {
+        let query = r"SELECT * FROM tag_reports WHERE guild_id = $1 ORDER BY created_at ASC";
+
+        sqlx::query_as(query).bind(guild_id).fetch_all(&handler.pool).await
+    }
+
+    /// Resolves every report against a tag.
+    pub async fn delete_for_tag(handler: &DatabaseHandler, guild_id: i64, name: &str) -> Result<(), sqlx::Error> {
+        let query = r"DELETE FROM tag_reports WHERE guild_id = $1 AND name = $2";
+
+        sqlx::query(query)
+            .bind(guild_id)
+            .bind(name)
+            .execute(&handler.pool)
+            .await?;
+
+        Ok(())
+    }
+}
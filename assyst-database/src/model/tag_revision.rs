@@ -0,0 +1,127 @@
+use sqlx::PgConnection;
+
+use crate::DatabaseHandler;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagRevisionKind {
+    Create,
+    Edit,
+    Delete,
+}
+impl TagRevisionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TagRevisionKind::Create => "create",
+            TagRevisionKind::Edit => "edit",
+            TagRevisionKind::Delete => "delete",
+        }
+    }
+}
+
+/// A snapshot of a tag, recorded whenever it is created, edited or deleted.
+///
+/// Table schema:
+/// ```sql
+/// CREATE TABLE tag_revisions (
+///     guild_id BIGINT NOT NULL,
+///     name TEXT NOT NULL,
+///     revision BIGINT NOT NULL,
+///     kind TEXT NOT NULL,
+///     data TEXT NOT NULL,
+///     author BIGINT NOT NULL,
+///     created_at BIGINT NOT NULL,
+///     PRIMARY KEY (guild_id, name, revision)
+/// );
+/// ```
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct TagRevision {
+    pub guild_id: i64,
+    pub name: String,
+    pub revision: i64,
+    pub kind: String,
+    /// Contents of the tag after this revision (or before it, for deletions)
+    pub data: String,
+    /// The user who made this change
+    pub author: i64,
+    pub created_at: i64,
+}
+impl TagRevision {
+    /// Records a new revision for a tag, numbered after the latest existing one.
+    ///
+    /// This is ran in the same transaction as the change to the tag, after it, so that the row lock
+    /// taken by the change orders concurrent revisions of the same tag.
+    pub(crate) async fn record(
+        conn: &mut PgConnection,
+        guild_id: i64,
+        name: &str,
+        kind: TagRevisionKind,
+        data: &str,
+        author: i64,
+    ) -> Result<(), sqlx::Error> {
+        let query = r"INSERT INTO tag_revisions
+            SELECT $1, $2, COALESCE(MAX(revision), 0) + 1, $3, $4, $5, (EXTRACT(EPOCH FROM now()) * 1000)::BIGINT
+            FROM tag_revisions WHERE guild_id = $1 AND name = $2";
+
+        sqlx::query(query)
+            .bind(guild_id)
+            .bind(name)
+            .bind(kind.as_str())
+            .bind(data)
+            .bind(author)
+            .execute(conn)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get(
+        handler: &DatabaseHandler,
+        guild_id: i64,
+        name: &str,
+        revision: i64,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let query = r"SELECT * FROM tag_revisions WHERE guild_id = $1 AND name = $2 AND revision = $3";
+
+        sqlx::query_as(query)
+            .bind(guild_id)
+            .bind(name)
+            .bind(revision)
+            .fetch_optional(&handler.pool)
+            .await
+    }
+
+    pub async fn get_latest_of_kind(
+        handler: &DatabaseHandler,
+        guild_id: i64,
+        name: &str,
+        kind: TagRevisionKind,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let query = r"SELECT * FROM tag_revisions WHERE guild_id = $1 AND name = $2 AND kind = $3 ORDER BY revision DESC LIMIT 1";
+
+        sqlx::query_as(query)
+            .bind(guild_id)
+            .bind(name)
+            .bind(kind.as_str())
+            .fetch_optional(&handler.pool)
+            .await
+    }
+
+    pub async fn get_paged(
+        handler: &DatabaseHandler,
+        guild_id: i64,
+        name: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let query =
+            r"SELECT * FROM tag_revisions WHERE guild_id = $1 AND name = $2 ORDER BY revision DESC OFFSET $3 LIMIT $4";
+
+        sqlx::query_as(query)
+            .bind(guild_id)
+            .bind(name)
+            .bind(offset)
+            .bind(limit)
+            .fetch_all(&handler.pool)
+            .await
+    }
+}
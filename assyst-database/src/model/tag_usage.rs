@@ -0,0 +1,90 @@
+use crate::DatabaseHandler;
+
+/// How many times each user has ran a tag. Totals and unique users are aggregated from these rows.
+///
+/// Table schema:
+/// ```sql
+/// CREATE TABLE tag_uses (
+///     guild_id BIGINT NOT NULL,
+///     name TEXT NOT NULL,
+///     user_id BIGINT NOT NULL,
+///     uses BIGINT NOT NULL,
+///     last_used BIGINT NOT NULL,
+///     PRIMARY KEY (guild_id, name, user_id)
+/// );
+/// ```
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct TagUsage {
+    pub guild_id: i64,
+    pub name: String,
+    pub user_id: i64,
+    pub uses: i64,
+    pub last_used: i64,
+}
+impl TagUsage {
+    pub async fn record(
+        handler: &DatabaseHandler,
+        guild_id: i64,
+        name: &str,
+        user_id: i64,
+        timestamp: i64,
+    ) -> Result<(), sqlx::Error> {
+        let query = r"INSERT INTO tag_uses VALUES ($1, $2, $3, 1, $4) ON CONFLICT (guild_id, name, user_id) DO UPDATE SET uses = tag_uses.uses + 1, last_used = $4";
+
+        sqlx::query(query)
+            .bind(guild_id)
+            .bind(name)
+            .bind(user_id)
+            .bind(timestamp)
+            .execute(&handler.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_for_tag(handler: &DatabaseHandler, guild_id: i64, name: &str) -> Result<(), sqlx::Error> {
+        let query = r"DELETE FROM tag_uses WHERE guild_id = $1 AND name = $2";
+
+        sqlx::query(query)
+            .bind(guild_id)
+            .bind(name)
+            .execute(&handler.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Usage of a single tag, aggregated over all of its users.
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct TagUsageStats {
+    pub name: String,
+    pub uses: i64,
+    pub unique_users: i64,
+    pub last_used: i64,
+}
+impl TagUsageStats {
+    pub async fn get(handler: &DatabaseHandler, guild_id: i64, name: &str) -> Result<Option<Self>, sqlx::Error> {
+        let query = r"SELECT name, SUM(uses)::BIGINT AS uses, COUNT(*) AS unique_users, MAX(last_used) AS last_used FROM tag_uses WHERE guild_id = $1 AND name = $2 GROUP BY name";
+
+        sqlx::query_as(query)
+            .bind(guild_id)
+            .bind(name)
+            .fetch_optional(&handler.pool)
+            .await
+    }
+
+    pub async fn get_top_in_guild(
+        handler: &DatabaseHandler,
+        guild_id: i64,
+        limit: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let query = r"SELECT name, SUM(uses)::BIGINT AS uses, COUNT(*) AS unique_users, MAX(last_used) AS last_used FROM tag_uses WHERE guild_id = $1 GROUP BY name ORDER BY uses DESC LIMIT $2";
+
+        sqlx::query_as(query)
+            .bind(guild_id)
+            .bind(limit)
+            .fetch_all(&handler.pool)
+            .await
+    }
+}
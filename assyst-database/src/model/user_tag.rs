@@ -0,0 +1,80 @@
+use crate::{is_unique_violation, DatabaseHandler};
+
+/// A personal tag, owned by a user and usable in any server or DM.
+///
+/// Table schema:
+/// ```sql
+/// CREATE TABLE user_tags (
+///     user_id BIGINT NOT NULL,
+///     name TEXT NOT NULL,
+///     data TEXT NOT NULL,
+///     created_at BIGINT NOT NULL,
+///     PRIMARY KEY (user_id, name)
+/// );
+/// ```
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct UserTag {
+    pub user_id: i64,
+    pub name: String,
+    pub data: String,
+    pub created_at: i64,
+}
+impl UserTag {
+    pub async fn get(handler: &DatabaseHandler, user_id: i64, name: &str) -> Result<Option<Self>, sqlx::Error> {
+        let query = r"SELECT * FROM user_tags WHERE user_id = $1 AND name = $2";
+
+        sqlx::query_as(query)
+            .bind(user_id)
+            .bind(name)
+            .fetch_optional(&handler.pool)
+            .await
+    }
+
+    pub async fn get_for_user(handler: &DatabaseHandler, user_id: i64) -> Result<Vec<Self>, sqlx::Error> {
+        let query = r"SELECT * FROM user_tags WHERE user_id = $1 ORDER BY name ASC";
+
+        sqlx::query_as(query).bind(user_id).fetch_all(&handler.pool).await
+    }
+
+    pub async fn set(&self, handler: &DatabaseHandler) -> Result<bool, sqlx::Error> {
+        let query = r"INSERT INTO user_tags VALUES ($1, $2, $3, $4)";
+
+        sqlx::query(query)
+            .bind(self.user_id)
+            .bind(&self.name)
+            .bind(&self.data)
+            .bind(self.created_at)
+            .execute(&handler.pool)
+            .await
+            .map(|_| true)
+            .or_else(|e| if is_unique_violation(&e) { Ok(false) } else { Err(e) })
+    }
+
+    pub async fn edit(
+        handler: &DatabaseHandler,
+        user_id: i64,
+        name: &str,
+        new_content: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let query = r"UPDATE user_tags SET data = $1 WHERE user_id = $2 AND name = $3";
+
+        sqlx::query(query)
+            .bind(new_content)
+            .bind(user_id)
+            .bind(name)
+            .execute(&handler.pool)
+            .await
+            .map(|r| r.rows_affected() > 0)
+    }
+
+    pub async fn delete(handler: &DatabaseHandler, user_id: i64, name: &str) -> Result<bool, sqlx::Error> {
+        let query = r"DELETE FROM user_tags WHERE user_id = $1 AND name = $2";
+
+        sqlx::query(query)
+            .bind(user_id)
+            .bind(name)
+            .execute(&handler.pool)
+            .await
+            .map(|r| r.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,153 @@
+use std::cmp::Ordering;
+use std::io::{Cursor, Read};
+
+use anyhow::{Context, bail};
+use assyst_common::util::filetype::{Type, get_sig};
+use zip::ZipArchive;
+
+/// Maximum total size of the frames in an archive, once decompressed.
+const MAX_FRAMES_SIZE_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Reads the frames out of a zip archive, such as one made by the `frames` command, ordered by
+/// file name. Files that are not images, and folders, are ignored.
+///
+/// Fails if the archive has more than `max_frames` files, which is checked before anything is
+/// decompressed.
+pub fn read_frames(archive: Vec<u8>, max_frames: u64) -> anyhow::Result<Vec<Vec<u8>>> {
+    let mut archive = ZipArchive::new(Cursor::new(archive)).context("The provided file is not a zip archive.")?;
+
+    let mut names = archive
+        .file_names()
+        .filter(|name| !name.ends_with('/') && !is_hidden(name))
+        .map(ToOwned::to_owned)
+        .collect::<Vec<_>>();
+    if names.len() as u64 > max_frames {
+        bail!(
+            "The archive has {} files, but at most {max_frames} frames can be used with your current tier.",
+            names.len()
+        );
+    }
+    names.sort_by(|a, b| natural_cmp(a, b));
+
+    let mut frames = Vec::with_capacity(names.len());
+    let mut total_size = 0;
+    for name in names {
+        let file = archive.by_name(&name)?;
+
+        let mut frame = Vec::new();
+        file.take(MAX_FRAMES_SIZE_BYTES - total_size + 1)
+            .read_to_end(&mut frame)
+            .with_context(|| format!("Failed to read {name} from the archive"))?;
+        total_size += frame.len() as u64;
+        if total_size > MAX_FRAMES_SIZE_BYTES {
+            bail!(
+                "The frames in the archive are too large (limit: {} MiB).",
+                MAX_FRAMES_SIZE_BYTES / 1024 / 1024
+            );
+        }
+
+        if matches!(get_sig(&frame), Some(Type::PNG | Type::JPEG | Type::GIF | Type::WEBP)) {
+            frames.push(frame);
+        }
+    }
+
+    if frames.is_empty() {
+        bail!("The archive doesn't contain any images.");
+    }
+
+    Ok(frames)
+}
+
+/// Whether a file is hidden, or metadata added by an archiver, like `__MACOSX/` folders.
+fn is_hidden(name: &str) -> bool {
+    name.split('/')
+        .any(|component| component.starts_with('.') || component == "__MACOSX")
+}
+
+/// Splits a file name into runs of digits and runs of everything else.
+fn chunks(name: &str) -> impl Iterator<Item = &str> {
+    let mut rest = name;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+
+        let digits = is_number(rest);
+        let end = rest.find(|c: char| c.is_ascii_digit() != digits).unwrap_or(rest.len());
+
+        let (chunk, tail) = rest.split_at(end);
+        rest = tail;
+        Some(chunk)
+    })
+}
+
+fn is_number(chunk: &str) -> bool {
+    chunk.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Compares file names with the numbers in them ordered by value, so that `frame-2.png` comes
+/// before `frame-10.png`.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = chunks(a);
+    let mut b = chunks(b);
+
+    loop {
+        let ordering = match (a.next(), b.next()) {
+            (Some(x), Some(y)) if is_number(x) && is_number(y) => {
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+            },
+            (Some(x), Some(y)) => x.cmp(y),
+            (x, y) => return x.is_some().cmp(&y.is_some()),
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+
+    use super::*;
+
+    const PNG: &[u8] = &[137, 80, 78, 71, 13, 10, 26, 10];
+
+    fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in files {
+            writer.start_file(*name, SimpleFileOptions::default()).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn frames_are_ordered_by_number() {
+        let frame = |n: u8| [PNG, &[n]].concat();
+        let archive = zip(&[
+            ("frame-10.png", &frame(10)),
+            ("frame-2.png", &frame(2)),
+            ("__MACOSX/._frame-1.png", &frame(0)),
+            ("frame-1.png", &frame(1)),
+            ("notes.txt", b"not a frame"),
+        ]);
+
+        let frames = read_frames(archive, 10).unwrap();
+        assert_eq!(frames, [frame(1), frame(2), frame(10)]);
+    }
+
+    #[test]
+    fn frame_count_is_limited() {
+        let archive = zip(&[("1.png", PNG), ("2.png", PNG), ("3.png", PNG)]);
+
+        assert!(read_frames(archive.clone(), 2).is_err());
+        assert_eq!(read_frames(archive, 3).unwrap().len(), 3);
+        assert!(read_frames(b"not a zip".to_vec(), 3).is_err());
+    }
+}
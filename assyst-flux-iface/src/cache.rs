@@ -0,0 +1,172 @@
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+use moka::sync::Cache;
+use sha2::{Digest, Sha256};
+
+use crate::flux_request::{FluxRequest, FluxStep};
+
+/// Maximum total size of all cached outputs.
+pub const MAX_CACHE_SIZE_BYTES: u64 = 256 * 1024 * 1024;
+/// Outputs larger than this are not cached, so that a few huge videos can't evict everything else.
+pub const MAX_CACHED_OUTPUT_SIZE_BYTES: usize = 16 * 1024 * 1024;
+/// How long outputs are cached for.
+pub const CACHE_TIME_TO_LIVE: Duration = Duration::from_secs(60 * 60);
+
+/// Operations known to always give the same output for the same inputs and options. Requests with
+/// any other operation, including random ones like `scramble` and ones added to Flux later, are
+/// never cached.
+const DETERMINISTIC_OPERATIONS: &[&str] = &[
+    "ah-shit",
+    "april-fools",
+    "assemble",
+    "back-tattoo",
+    "bass-boost",
+    "billboard",
+    "bloom",
+    "blur",
+    "book",
+    "caption",
+    "circuitboard",
+    "collage",
+    "compare",
+    "concat",
+    "cut",
+    "extract-audio",
+    "femurbreaker",
+    "fisheye",
+    "flag",
+    "flag2",
+    "flip",
+    "flop",
+    "fortune-cookie",
+    "frames",
+    "gif",
+    "globe",
+    "grayscale",
+    "grid",
+    "heart-locket",
+    "hstack",
+    "invert",
+    "jpeg",
+    "meme",
+    "motivate",
+    "mute-audio",
+    "neon",
+    "nightcore",
+    "overlay",
+    "ping-pong",
+    "pitch",
+    "pixelate",
+    "rainbow",
+    "resize",
+    "reverb",
+    "reverse",
+    "rotate",
+    "set-loop",
+    "siren",
+    "speed",
+    "spin",
+    "sweden",
+    "swirl",
+    "terraria",
+    "toaster",
+    "trim",
+    "uncaption",
+    "valentine",
+    "volume",
+    "vstack",
+    "wormhole",
+    "zoom",
+    "zoom-blur",
+];
+
+/// Cache of Flux outputs, keyed by a SHA-256 digest of the inputs, operations and limits of the
+/// request that created them.
+///
+/// Entries are weighed by their size and evicted by moka's size-aware TinyLFU policy once
+/// [`MAX_CACHE_SIZE_BYTES`] is reached, or after [`CACHE_TIME_TO_LIVE`].
+pub struct FluxCache(Cache<String, Arc<Vec<u8>>>);
+impl FluxCache {
+    #[must_use]
+    pub fn new() -> Self {
+        Self(
+            Cache::builder()
+                .max_capacity(MAX_CACHE_SIZE_BYTES)
+                .weigher(|key: &String, value: &Arc<Vec<u8>>| {
+                    u32::try_from(key.len() + value.len()).unwrap_or(u32::MAX)
+                })
+                .time_to_live(CACHE_TIME_TO_LIVE)
+                .build(),
+        )
+    }
+
+    /// Builds the cache key of a request, or `None` if its output must not be cached.
+    #[must_use]
+    pub fn key(request: &FluxRequest) -> Option<String> {
+        let mut hasher = Sha256::new();
+        let mut has_output = false;
+
+        for step in &request.0 {
+            match step {
+                FluxStep::Input(input) => hash_field(&mut hasher, b'i', input),
+                FluxStep::Operation((operation, options)) => {
+                    if !DETERMINISTIC_OPERATIONS.contains(&operation.as_str()) {
+                        return None;
+                    }
+
+                    // options are a hashmap, so sort them to not depend on iteration order
+                    let mut options = options.iter().collect::<Vec<_>>();
+                    options.sort();
+
+                    let mut field = format!("{operation}[");
+                    for (name, value) in options {
+                        let _ = write!(field, "{name:?}={value:?},");
+                    }
+                    field.push(']');
+                    hash_field(&mut hasher, b'o', field.as_bytes());
+                },
+                FluxStep::Output => has_output = true,
+                FluxStep::OutputFormat(format) => hash_field(&mut hasher, b'f', format.as_str().as_bytes()),
+                FluxStep::ImagePageLimit(limit) => hash_field(&mut hasher, b'p', &limit.to_le_bytes()),
+                FluxStep::ResolutionLimit((width, height)) => {
+                    hash_field(&mut hasher, b'r', format!("{width}x{height}").as_bytes());
+                },
+                FluxStep::VideoDecodeDisabled => hash_field(&mut hasher, b'v', &[]),
+                FluxStep::Info | FluxStep::Version => return None,
+            }
+        }
+
+        has_output.then(|| format!("{:x}", hasher.finalize()))
+    }
+
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.0.get(key).map(|output| output.as_ref().clone())
+    }
+
+    pub fn insert(&self, key: String, output: &[u8]) {
+        if output.len() <= MAX_CACHED_OUTPUT_SIZE_BYTES {
+            self.0.insert(key, Arc::new(output.to_vec()));
+        }
+    }
+
+    #[must_use]
+    pub fn entry_count(&self) -> u64 {
+        self.0.entry_count()
+    }
+}
+impl Default for FluxCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Adds a step to a cache key. Every field is tagged with the kind of step and prefixed by its
+/// length, so that different requests never hash the same bytes.
+fn hash_field(hasher: &mut Sha256, tag: u8, data: &[u8]) {
+    hasher.update([tag]);
+    hasher.update((data.len() as u64).to_le_bytes());
+    hasher.update(data);
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, bail};
+
+use crate::jobs::{ChainOptionKind, find_chain_operation};
+
+/// Maximum number of operations in a single chain.
+pub const MAX_CHAIN_STEPS: usize = 10;
+
+/// A validated step of a chain, as (Flux operation name, options).
+pub type ChainStep = (String, HashMap<String, String>);
+
+/// Parses a chain of operations, such as `caption "hi" | speed 2 | reverse`, validating every
+/// step against [`crate::jobs::CHAIN_OPERATIONS`].
+///
+/// Steps are separated by `|` and each one is an operation name followed by its options, in order.
+/// Double quotes group words together, and `\` escapes the next character. If the last option of an
+/// operation is text, it takes the rest of the step, so it does not need to be quoted.
+pub fn parse_chain(input: &str) -> anyhow::Result<Vec<ChainStep>> {
+    let steps = tokenize(input)?;
+    if steps.len() > MAX_CHAIN_STEPS {
+        bail!("A chain can have at most {MAX_CHAIN_STEPS} steps.");
+    }
+
+    steps
+        .into_iter()
+        .enumerate()
+        .map(|(index, words)| parse_step(index + 1, words))
+        .collect()
+}
+
+/// Splits a chain into its steps, and each step into its words.
+fn tokenize(input: &str) -> anyhow::Result<Vec<Vec<String>>> {
+    let mut steps = vec![];
+    let mut words = vec![];
+    let mut word = String::new();
+    // separate from `word.is_empty()` so that `""` is an (empty) word of its own
+    let mut in_word = false;
+    let mut quoted = false;
+
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                word.push(chars.next().context("The chain ends with an unfinished escape.")?);
+                in_word = true;
+            },
+            '"' => {
+                quoted = !quoted;
+                in_word = true;
+            },
+            '|' if !quoted => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+                steps.push(std::mem::take(&mut words));
+            },
+            c if c.is_whitespace() && !quoted => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            },
+            c => {
+                word.push(c);
+                in_word = true;
+            },
+        }
+    }
+
+    if quoted {
+        bail!("The chain has an unclosed quote.");
+    }
+    if in_word {
+        words.push(word);
+    }
+    steps.push(words);
+
+    Ok(steps)
+}
+
+fn parse_step(index: usize, words: Vec<String>) -> anyhow::Result<ChainStep> {
+    let mut words = words.into_iter();
+    let Some(name) = words.next() else {
+        bail!("Step {index} of the chain is empty.");
+    };
+    let Some(operation) = find_chain_operation(&name) else {
+        bail!("Step {index}: unknown operation `{name}`.");
+    };
+    let mut values = words.collect::<Vec<_>>();
+
+    if let Some((_, ChainOptionKind::Text)) = operation.options.last()
+        && values.len() > operation.options.len()
+    {
+        let rest = values.split_off(operation.options.len() - 1).join(" ");
+        values.push(rest);
+    }
+
+    let option_names = || {
+        operation
+            .options
+            .iter()
+            .map(|(name, _)| format!("`{name}`"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    if values.len() > operation.options.len() {
+        if operation.options.is_empty() {
+            bail!("Step {index}: `{}` does not take any options.", operation.name);
+        }
+        bail!(
+            "Step {index}: `{}` takes at most {} option(s): {}.",
+            operation.name,
+            operation.options.len(),
+            option_names()
+        );
+    }
+    if values.len() < operation.required {
+        bail!(
+            "Step {index}: `{}` needs at least {} option(s): {}.",
+            operation.name,
+            operation.required,
+            option_names()
+        );
+    }
+
+    let mut options = HashMap::new();
+    for (&(option, kind), value) in operation.options.iter().zip(values) {
+        let valid = match kind {
+            ChainOptionKind::Text => true,
+            ChainOptionKind::Integer => value.parse::<i64>().is_ok(),
+            ChainOptionKind::Float => value.parse::<f64>().is_ok_and(f64::is_finite),
+        };
+        if !valid {
+            bail!(
+                "Step {index}: `{option}` of `{}` must be a {}, but `{value}` was given.",
+                operation.name,
+                if kind == ChainOptionKind::Integer {
+                    "whole number"
+                } else {
+                    "number"
+                }
+            );
+        }
+
+        options.insert(option.to_owned(), value);
+    }
+
+    Ok((operation.name.to_owned(), options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_steps_and_options() {
+        let steps = parse_chain(r#"caption "hi there" | speed 2 | reverse"#).unwrap();
+
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[0].0, "caption");
+        assert_eq!(steps[0].1["text"], "hi there");
+        assert_eq!(steps[1].0, "speed");
+        assert_eq!(steps[1].1["multiplier"], "2");
+        assert_eq!(steps[2].0, "reverse");
+        assert!(steps[2].1.is_empty());
+    }
+
+    #[test]
+    fn trailing_text_takes_rest_of_step() {
+        let err = parse_chain("caption a | b c | zoom_blur").unwrap_err();
+        assert!(err.to_string().contains("unknown operation `b`"));
+
+        let steps = parse_chain(r"caption hello \| world | zoom_blur").unwrap();
+        assert_eq!(steps[0].1["text"], "hello | world");
+        assert_eq!(steps[1].0, "zoom-blur");
+    }
+
+    #[test]
+    fn rejects_invalid_steps() {
+        assert!(parse_chain("").is_err());
+        assert!(parse_chain("reverse |").is_err());
+        assert!(parse_chain("reverse 2").is_err());
+        assert!(parse_chain("caption").is_err());
+        assert!(parse_chain("speed fast").is_err());
+        assert!(parse_chain("rotate 1.5").is_err());
+        assert!(parse_chain(r#"caption "hi"#).is_err());
+        assert!(parse_chain(&["flip"; MAX_CHAIN_STEPS + 1].join(" | ")).is_err());
+    }
+}
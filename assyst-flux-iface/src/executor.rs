@@ -0,0 +1,184 @@
+use std::process::Stdio;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use anyhow::{Context, bail};
+use assyst_common::util::string_from_likely_utf8;
+use async_trait::async_trait;
+use libc::pid_t;
+use tokio::fs;
+use tokio::time::timeout;
+
+use crate::flux_request::{FluxRequest, FluxStep};
+use crate::jobs::FluxResult;
+use crate::sandbox::{self, Cgroup, SandboxLimits, SandboxViolation};
+use crate::workspace::JobWorkspace;
+use crate::{COMPILING, flux_command, step_args};
+
+/// Runs Flux requests for a [`FluxHandler`](crate::FluxHandler).
+#[async_trait]
+pub trait FluxExecutor: Send + Sync {
+    /// Runs a request, giving up after `time_limit` and limiting Flux to `sandbox`, if any.
+    async fn execute(&self, request: FluxRequest, time_limit: Duration, sandbox: Option<&SandboxLimits>) -> FluxResult;
+}
+
+/// Runs every request in a new Flux process.
+#[derive(Default)]
+pub struct ProcessExecutor;
+#[async_trait]
+impl FluxExecutor for ProcessExecutor {
+    async fn execute(&self, request: FluxRequest, time_limit: Duration, sandbox: Option<&SandboxLimits>) -> FluxResult {
+        if COMPILING.load(Ordering::Relaxed) {
+            bail!("The image service is still preparing. Try again in a few seconds.");
+        }
+
+        // removed, along with all inputs and outputs, on return
+        let mut workspace = JobWorkspace::create().await?;
+        let mut output_file_path = None;
+        let mut args: Vec<String> = vec![];
+
+        for step in request.0 {
+            match step {
+                FluxStep::Input(i) => {
+                    let path = workspace.write_input(&i).await?;
+
+                    args.push("-i".to_owned());
+                    args.push(path.to_string_lossy().into_owned());
+                },
+                FluxStep::Output => {
+                    let path = workspace.output_path();
+                    args.push(path.to_string_lossy().into_owned());
+                    output_file_path = Some(path);
+                },
+                step => args.extend(step_args(&step)),
+            }
+        }
+
+        let mut command = flux_command();
+        command.args(args);
+        command.stdout(Stdio::piped());
+        command.stderr(Stdio::piped());
+
+        // must outlive the process, since dropping it kills everything left inside of it
+        let cgroup = match sandbox {
+            Some(limits) => Cgroup::create(limits).await?,
+            None => None,
+        };
+        if let Some(limits) = sandbox {
+            sandbox::apply(&mut command, limits, cgroup.as_ref());
+        }
+
+        let spawn = command.spawn().context("Failed to execute flux")?;
+        let id = spawn.id();
+        let output = timeout(time_limit, spawn.wait_with_output()).await;
+
+        let output = if let Ok(o) = output {
+            o
+        } else {
+            // send SIGTERM to flux to clean up child processes
+            if let Some(id) = id {
+                unsafe { libc::kill(id as pid_t, libc::SIGTERM) };
+            };
+            bail!("The operation timed out");
+        }
+        .context("Failed to execute flux")?;
+
+        if !output.status.success() {
+            let stderr = string_from_likely_utf8(output.stderr);
+
+            if let Some(limits) = sandbox
+                && let Some(violation) = SandboxViolation::detect(output.status, &stderr, cgroup.as_ref())
+            {
+                bail!("{}", violation.message(limits));
+            }
+
+            bail!("{} ({})", stderr.trim(), output.status.to_string());
+        }
+
+        let output = if let Some(output_file_path) = output_file_path {
+            fs::read(&output_file_path)
+                .await
+                .context("Failed to read output file")?
+        } else {
+            output.stdout
+        };
+
+        Ok(output)
+    }
+}
+
+/// Executors for tests, that never run Flux.
+#[cfg(test)]
+pub mod fake {
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+
+    use super::FluxExecutor;
+    use crate::flux_request::{FluxRequest, FluxStep};
+    use crate::jobs::FluxResult;
+    use crate::sandbox::SandboxLimits;
+    use crate::step_args;
+
+    /// A request received by a [`FakeExecutor`].
+    #[derive(Clone, Debug)]
+    pub struct RecordedRequest {
+        /// Flux arguments of every step other than inputs and outputs, in order
+        pub args: Vec<String>,
+        pub inputs: Vec<Vec<u8>>,
+        pub has_output: bool,
+        pub time_limit: Duration,
+        pub sandbox: Option<SandboxLimits>,
+    }
+
+    /// Executor that never runs Flux. It records every request it receives and returns the same
+    /// output for all of them, so that jobs can be tested without Flux.
+    pub struct FakeExecutor {
+        output: Vec<u8>,
+        requests: Mutex<Vec<RecordedRequest>>,
+    }
+    impl FakeExecutor {
+        #[must_use]
+        pub fn new(output: Vec<u8>) -> Self {
+            Self {
+                output,
+                requests: Mutex::new(vec![]),
+            }
+        }
+
+        /// All requests received so far, oldest first.
+        #[must_use]
+        pub fn requests(&self) -> Vec<RecordedRequest> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+    #[async_trait]
+    impl FluxExecutor for FakeExecutor {
+        async fn execute(
+            &self,
+            request: FluxRequest,
+            time_limit: Duration,
+            sandbox: Option<&SandboxLimits>,
+        ) -> FluxResult {
+            let mut recorded = RecordedRequest {
+                args: vec![],
+                inputs: vec![],
+                has_output: false,
+                time_limit,
+                sandbox: sandbox.copied(),
+            };
+
+            for step in request.0 {
+                match step {
+                    FluxStep::Input(input) => recorded.inputs.push(input),
+                    FluxStep::Output => recorded.has_output = true,
+                    step => recorded.args.extend(step_args(&step)),
+                }
+            }
+
+            self.requests.lock().unwrap().push(recorded);
+            Ok(self.output.clone())
+        }
+    }
+}
@@ -1,7 +1,32 @@
 use std::collections::HashMap;
 
+use assyst_common::util::filetype::Type;
+
 use super::limits::LimitData;
 
+/// Formats that the output of a Flux job can be requested in.
+pub const OUTPUT_FORMATS: [Type; 5] = [Type::GIF, Type::PNG, Type::WEBP, Type::MP4, Type::WEBM];
+
+/// Parses a user-provided output format, such as `webp`, if it is one of [`OUTPUT_FORMATS`].
+#[must_use]
+pub fn parse_output_format(format: &str) -> Option<Type> {
+    let format = format.trim().trim_start_matches('.');
+    OUTPUT_FORMATS
+        .into_iter()
+        .find(|output| output.as_str().eq_ignore_ascii_case(format))
+}
+
+tokio::task_local! {
+    /// Output format of all Flux jobs ran by the current task, if set. Otherwise, Flux picks the
+    /// format based on the input.
+    pub static OUTPUT_FORMAT: Option<Type>;
+}
+
+/// Output format set by [`OUTPUT_FORMAT`] for the current task, if any.
+pub(crate) fn requested_output_format() -> Option<Type> {
+    OUTPUT_FORMAT.try_with(|format| *format).ok().flatten()
+}
+
 /// A step in a Flux execution.
 pub enum FluxStep {
     /// Input file. Saves the file and passes to Flux as `-i path`. Input must be the first step.
@@ -10,6 +35,8 @@ pub enum FluxStep {
     Operation((String, HashMap<String, String>)),
     /// Output. Passes to Flux as `path` at the end. Output must be the last step.
     Output,
+    /// Format of the output. Passes to Flux as `--format ext`. Must come before the output.
+    OutputFormat(Type),
     /// Frame limit of inputs. Inputs will have additional frames removed.
     ImagePageLimit(u64),
     /// Resolution limit of input. Input will shrink, preserving aspect ratio, to fit this.
@@ -49,7 +76,22 @@ impl FluxRequest {
         self.0.push(FluxStep::Operation((name, options)));
     }
 
+    /// Adds the output, in the format set by [`OUTPUT_FORMAT`] for the current task, if any.
     pub fn output(&mut self) {
+        match requested_output_format() {
+            Some(format) => self.output_as(format),
+            None => self.0.push(FluxStep::Output),
+        }
+    }
+
+    /// Adds the output, in the format set by [`OUTPUT_FORMAT`] for the current task, or `default`.
+    pub fn output_or(&mut self, default: Type) {
+        self.output_as(requested_output_format().unwrap_or(default));
+    }
+
+    /// Adds the output in `format`, ignoring [`OUTPUT_FORMAT`].
+    pub fn output_as(&mut self, format: Type) {
+        self.0.push(FluxStep::OutputFormat(format));
         self.0.push(FluxStep::Output);
     }
 
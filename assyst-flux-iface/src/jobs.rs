@@ -1,12 +1,16 @@
 use std::collections::HashMap;
 use std::time::Duration;
 
-use assyst_common::util::string_from_likely_utf8;
+use anyhow::bail;
+use assyst_common::util::filetype::Type;
+use assyst_common::util::{format_duration, string_from_likely_utf8};
 use serde::Deserialize;
 use serde_json::from_str;
 
 use super::FluxHandler;
+use super::archive::read_frames;
 use super::flux_request::FluxRequest;
+use super::limits::{LimitData, MAX_INPUTS};
 
 #[derive(Deserialize)]
 pub struct ImageInfo {
@@ -36,13 +40,132 @@ pub enum MediaInfo {
 
 pub type FluxResult = anyhow::Result<Vec<u8>>;
 
+/// Kind of value that an option of a chainable operation takes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChainOptionKind {
+    Text,
+    Integer,
+    Float,
+}
+
+/// An operation that can be used as a step of a chain, see [`FluxHandler::chain`].
+pub struct ChainOperation {
+    /// Name of the operation, as passed to Flux
+    pub name: &'static str,
+    /// Options of the operation, in the order they are given in a step
+    pub options: &'static [(&'static str, ChainOptionKind)],
+    /// How many of the options must be given
+    pub required: usize,
+}
+
+const fn op(name: &'static str) -> ChainOperation {
+    ChainOperation {
+        name,
+        options: &[],
+        required: 0,
+    }
+}
+
+const fn op_with(
+    name: &'static str,
+    options: &'static [(&'static str, ChainOptionKind)],
+    required: usize,
+) -> ChainOperation {
+    ChainOperation {
+        name,
+        options,
+        required,
+    }
+}
+
+/// All operations that take a single input and can be chained together.
+pub const CHAIN_OPERATIONS: &[ChainOperation] = &[
+    op("ah-shit"),
+    op("april-fools"),
+    op("back-tattoo"),
+    op("billboard"),
+    op_with(
+        "bloom",
+        &[
+            ("radius", ChainOptionKind::Integer),
+            ("sharpness", ChainOptionKind::Integer),
+            ("brightness", ChainOptionKind::Integer),
+        ],
+        0,
+    ),
+    op_with("blur", &[("strength", ChainOptionKind::Float)], 0),
+    op("book"),
+    op_with("caption", &[("text", ChainOptionKind::Text)], 1),
+    op("circuitboard"),
+    op("deepfry"),
+    op("drip"),
+    op("femurbreaker"),
+    op("fisheye"),
+    op("flag"),
+    op("flag2"),
+    op("flip"),
+    op("flop"),
+    op("fortune-cookie"),
+    op("frame-shift"),
+    op_with("ghost", &[("depth", ChainOptionKind::Integer)], 0),
+    op("gif"),
+    op("gif-magik"),
+    op("globe"),
+    op("grayscale"),
+    op_with("heart-locket", &[("text", ChainOptionKind::Text)], 1),
+    op("invert"),
+    op_with("jpeg", &[("quality", ChainOptionKind::Integer)], 0),
+    op("magik"),
+    op_with(
+        "meme",
+        &[("top", ChainOptionKind::Text), ("bottom", ChainOptionKind::Text)],
+        1,
+    ),
+    op_with(
+        "motivate",
+        &[("top", ChainOptionKind::Text), ("bottom", ChainOptionKind::Text)],
+        1,
+    ),
+    op("neon"),
+    op("paint"),
+    op("ping-pong"),
+    op_with("pixelate", &[("strength", ChainOptionKind::Float)], 0),
+    op("rainbow"),
+    op_with("resize", &[("scale", ChainOptionKind::Float)], 1),
+    op("reverse"),
+    op_with("rotate", &[("degrees", ChainOptionKind::Integer)], 0),
+    op("rubiks"),
+    op("scramble"),
+    op_with("set-loop", &[("loops", ChainOptionKind::Integer)], 1),
+    op("siren"),
+    op_with("speed", &[("multiplier", ChainOptionKind::Float)], 0),
+    op("spin"),
+    op_with("spread", &[("strength", ChainOptionKind::Integer)], 0),
+    op("sweden"),
+    op_with("swirl", &[("strength", ChainOptionKind::Float)], 0),
+    op("terraria"),
+    op("toaster"),
+    op_with("uncaption", &[("amount", ChainOptionKind::Text)], 0),
+    op("valentine"),
+    op("wormhole"),
+    op("zoom"),
+    op_with("zoom-blur", &[("power", ChainOptionKind::Float)], 0),
+];
+
+/// Finds a chainable operation by name. Underscores are treated as hyphens.
+#[must_use]
+pub fn find_chain_operation(name: &str) -> Option<&'static ChainOperation> {
+    let name = name.to_ascii_lowercase().replace('_', "-");
+    CHAIN_OPERATIONS.iter().find(|op| op.name == name)
+}
+
 impl FluxHandler {
     pub async fn ahshit(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
         let limits = self.get_request_limits(user_id, guild_id).await?;
 
         let request = FluxRequest::new_basic(media, &limits, "ah-shit");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn aprilfools(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -50,7 +173,53 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "april-fools");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
+    }
+
+    /// Builds a GIF, or the requested output format, out of the frames in a zip archive, see
+    /// [`read_frames`]. `delay` is the time between frames in milliseconds, and `loops` is the
+    /// number of times to loop, with -1 looping forever.
+    pub async fn assemble(
+        &self,
+        archive: Vec<u8>,
+        delay: Option<u64>,
+        loops: Option<i64>,
+        user_id: u64,
+        guild_id: Option<u64>,
+    ) -> FluxResult {
+        if let Some(delay) = delay
+            && !(10..=10_000).contains(&delay)
+        {
+            bail!("The delay must be between 10 and 10000 milliseconds.");
+        }
+        if let Some(loops) = loops
+            && loops < -1
+        {
+            bail!("The loop count must be -1 (forever) or more.");
+        }
+
+        let limits = self.get_request_limits(user_id, guild_id).await?;
+        let max_frames = limits.frames;
+        let frames = tokio::task::spawn_blocking(move || read_frames(archive, max_frames)).await??;
+
+        let mut request = FluxRequest(vec![]);
+        for frame in frames {
+            request.input(frame);
+        }
+        request.limits(&limits);
+
+        let mut options = HashMap::new();
+        if let Some(d) = delay {
+            options.insert("delay".to_owned(), d.to_string());
+        }
+        if let Some(l) = loops {
+            options.insert("loops".to_owned(), l.to_string());
+        }
+
+        request.operation("assemble".to_owned(), options);
+        request.output_or(Type::GIF);
+
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn back_tattoo(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -58,7 +227,36 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "back-tattoo");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
+    }
+
+    /// Boosts the bass of the audio track by `gain` decibels.
+    pub async fn bass_boost(
+        &self,
+        media: Vec<u8>,
+        gain: Option<f64>,
+        user_id: u64,
+        guild_id: Option<u64>,
+    ) -> FluxResult {
+        if let Some(gain) = gain
+            && !(1.0..=30.0).contains(&gain)
+        {
+            bail!("The gain must be between 1 and 30 decibels.");
+        }
+
+        let limits = self.get_request_limits(user_id, guild_id).await?;
+
+        let mut request = FluxRequest::new_with_input_and_limits(media, &limits);
+
+        let mut options = HashMap::new();
+        if let Some(g) = gain {
+            options.insert("gain".to_owned(), g.to_string());
+        }
+
+        request.operation("bass-boost".to_owned(), options);
+        request.output();
+
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn billboard(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -66,7 +264,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "billboard");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn bloom(
@@ -96,7 +294,7 @@ impl FluxHandler {
         request.operation("bloom".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn blur(&self, media: Vec<u8>, power: Option<f32>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -112,7 +310,7 @@ impl FluxHandler {
         request.operation("blur".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn book(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -120,7 +318,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "book");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn caption(
@@ -148,7 +346,28 @@ impl FluxHandler {
         request.operation("caption".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
+    }
+
+    /// Runs several operations, one after the other, in a single Flux process.\
+    /// Limits are only fetched (and so, any free tier 2 request only used) once for the whole
+    /// chain.
+    pub async fn chain(
+        &self,
+        media: Vec<u8>,
+        steps: Vec<(String, HashMap<String, String>)>,
+        user_id: u64,
+        guild_id: Option<u64>,
+    ) -> FluxResult {
+        let limits = self.get_request_limits(user_id, guild_id).await?;
+
+        let mut request = FluxRequest::new_with_input_and_limits(media, &limits);
+        for (operation, options) in steps {
+            request.operation(operation, options);
+        }
+        request.output();
+
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn circuitboard(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -156,7 +375,63 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "circuitboard");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
+    }
+
+    /// Arranges several images into a collage, sized to fit them as evenly as possible.
+    pub async fn collage(&self, media: Vec<Vec<u8>>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
+        self.multi_input("collage", media, HashMap::new(), user_id, guild_id)
+            .await
+    }
+
+    /// Overlays several images on top of each other, each with equal transparency.
+    pub async fn compare(&self, media: Vec<Vec<u8>>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
+        self.multi_input("compare", media, HashMap::new(), user_id, guild_id)
+            .await
+    }
+
+    /// Joins videos together, one after another.
+    pub async fn concat(&self, media: Vec<Vec<u8>>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
+        if media.len() < 2 {
+            bail!("This command needs at least 2 videos.");
+        }
+        if media.len() as u64 > MAX_INPUTS {
+            bail!("This command can use at most {MAX_INPUTS} videos.");
+        }
+
+        let limits = self.video_editing_limits(user_id, guild_id).await?;
+        check_max_inputs(media.len(), &limits)?;
+
+        let mut duration = Duration::ZERO;
+        let mut frames = 0;
+        for input in &media {
+            let info = self.video_info(input, &limits, user_id).await?;
+            duration += Duration::from_millis(info.duration_ms);
+            frames += info.frame_count;
+        }
+        check_clip_limits(duration, frames, &limits)?;
+
+        let mut request = FluxRequest(vec![]);
+        for input in media {
+            request.input(input);
+        }
+        request.limits(&limits);
+        request.operation("concat".to_owned(), HashMap::new());
+        request.output();
+
+        self.run_flux_queued(request, &limits, user_id).await
+    }
+
+    /// Removes the part of a video between `start` and `end`.
+    pub async fn cut(
+        &self,
+        media: Vec<u8>,
+        start: Duration,
+        end: Duration,
+        user_id: u64,
+        guild_id: Option<u64>,
+    ) -> FluxResult {
+        self.edit_range("cut", media, start, end, user_id, guild_id).await
     }
 
     pub async fn deepfry(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -164,7 +439,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "deepfry");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn drip(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -172,7 +447,18 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "drip");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
+    }
+
+    /// Extracts the audio track of a video as an MP3.
+    pub async fn extract_audio(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
+        let limits = self.get_request_limits(user_id, guild_id).await?;
+
+        let mut request = FluxRequest::new_with_input_and_limits(media, &limits);
+        request.operation("extract-audio".to_owned(), HashMap::new());
+        request.output_as(Type::MP3);
+
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn femurbreaker(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -180,7 +466,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "femurbreaker");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn fisheye(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -188,7 +474,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "fisheye");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn flag(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -196,7 +482,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "flag");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn flag2(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -204,7 +490,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "flag2");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn flip(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -212,7 +498,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "flip");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn flop(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -220,7 +506,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "flop");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn fortune_cookie(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -228,7 +514,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "fortune-cookie");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn frame_shift(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -236,7 +522,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "frame-shift");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn frames(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -244,7 +530,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "frames");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn ghost(&self, media: Vec<u8>, depth: Option<u64>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -260,7 +546,7 @@ impl FluxHandler {
         request.operation("ghost".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn gif(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -268,7 +554,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "gif");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn gif_magik(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -276,7 +562,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "gif-magik");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn globe(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -284,7 +570,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "globe");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn grayscale(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -292,7 +578,24 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "grayscale");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
+    }
+
+    /// Arranges several images into a grid, with `columns` images per row, or as close to a square
+    /// as possible by default.
+    pub async fn grid(
+        &self,
+        media: Vec<Vec<u8>>,
+        columns: Option<u64>,
+        user_id: u64,
+        guild_id: Option<u64>,
+    ) -> FluxResult {
+        let mut options = HashMap::new();
+        if let Some(c) = columns {
+            options.insert("columns".to_owned(), c.to_string());
+        }
+
+        self.multi_input("grid", media, options, user_id, guild_id).await
     }
 
     pub async fn heart_locket(&self, media: Vec<u8>, text: String, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -306,16 +609,20 @@ impl FluxHandler {
         request.operation("heart-locket".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
-    pub async fn image_info(&self, media: Vec<u8>) -> anyhow::Result<MediaInfo> {
-        let mut request = FluxRequest::default();
-        request.input(media);
-        request.info();
+    /// Places several images side by side, from left to right.
+    pub async fn hstack(&self, media: Vec<Vec<u8>>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
+        self.multi_input("hstack", media, HashMap::new(), user_id, guild_id)
+            .await
+    }
 
-        let out = self.run_flux(request, Duration::MAX).await?;
-        Ok(from_str::<MediaInfo>(&string_from_likely_utf8(out))?)
+    pub async fn image_info(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> anyhow::Result<MediaInfo> {
+        // only looks at the input, so isn't worth a free request
+        let limits = self.peek_request_limits(user_id, guild_id).await?;
+
+        self.media_info(media, &limits, user_id).await
     }
 
     pub async fn invert(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -323,7 +630,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "invert");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn jpeg(&self, media: Vec<u8>, quality: Option<u64>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -339,7 +646,7 @@ impl FluxHandler {
         request.operation("jpeg".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn magik(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -347,7 +654,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "magik");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn meme(
@@ -377,7 +684,7 @@ impl FluxHandler {
         request.operation("meme".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn motivate(
@@ -407,7 +714,16 @@ impl FluxHandler {
         request.operation("motivate".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
+    }
+
+    /// Removes the audio track of a video.
+    pub async fn mute_audio(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
+        let limits = self.get_request_limits(user_id, guild_id).await?;
+
+        let request = FluxRequest::new_basic(media, &limits, "mute-audio");
+
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn neon(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -415,7 +731,15 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "neon");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
+    }
+
+    pub async fn nightcore(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
+        let limits = self.get_request_limits(user_id, guild_id).await?;
+
+        let request = FluxRequest::new_basic(media, &limits, "nightcore");
+
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn overlay(&self, media: Vec<u8>, media2: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -427,7 +751,7 @@ impl FluxHandler {
         request.operation("overlay".to_owned(), HashMap::new());
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn paint(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -435,7 +759,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "paint");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn ping_pong(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -443,7 +767,26 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "ping-pong");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
+    }
+
+    /// Shifts the pitch of the audio track by `semitones`, without changing its speed.
+    pub async fn pitch(&self, media: Vec<u8>, semitones: f64, user_id: u64, guild_id: Option<u64>) -> FluxResult {
+        if !(-12.0..=12.0).contains(&semitones) {
+            bail!("The pitch must be between -12 and 12 semitones.");
+        }
+
+        let limits = self.get_request_limits(user_id, guild_id).await?;
+
+        let mut request = FluxRequest::new_with_input_and_limits(media, &limits);
+
+        let mut options = HashMap::new();
+        options.insert("semitones".to_owned(), semitones.to_string());
+
+        request.operation("pitch".to_owned(), options);
+        request.output();
+
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn pixelate(
@@ -465,7 +808,7 @@ impl FluxHandler {
         request.operation("pixelate".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn rainbow(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -473,7 +816,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "rainbow");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn resize_absolute(
@@ -495,7 +838,7 @@ impl FluxHandler {
         request.operation("resize".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn resize_scale(&self, media: Vec<u8>, scale: f32, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -509,7 +852,15 @@ impl FluxHandler {
         request.operation("resize".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
+    }
+
+    pub async fn reverb(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
+        let limits = self.get_request_limits(user_id, guild_id).await?;
+
+        let request = FluxRequest::new_basic(media, &limits, "reverb");
+
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn reverse(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -517,7 +868,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "reverse");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn rotate(
@@ -539,7 +890,7 @@ impl FluxHandler {
         request.operation("rotate".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn rubiks(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -547,7 +898,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "rubiks");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn set_loop(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>, loops: i64) -> FluxResult {
@@ -561,7 +912,7 @@ impl FluxHandler {
         request.operation("set-loop".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn scramble(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -569,7 +920,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "scramble");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn siren(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -577,7 +928,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "siren");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn speech_bubble(&self, media: Vec<u8>, solid: bool, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -593,7 +944,7 @@ impl FluxHandler {
         request.operation("speech-bubble".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn speed(
@@ -615,7 +966,7 @@ impl FluxHandler {
         request.operation("speed".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn spin(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -623,7 +974,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "spin");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn spread(
@@ -645,7 +996,7 @@ impl FluxHandler {
         request.operation("spread".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn sweden(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -653,7 +1004,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "sweden");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn swirl(
@@ -675,7 +1026,7 @@ impl FluxHandler {
         request.operation("swirl".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn terraria(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -683,7 +1034,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "terraria");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn toaster(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -691,7 +1042,19 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "toaster");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
+    }
+
+    /// Keeps only the part of a video between `start` and `end`.
+    pub async fn trim(
+        &self,
+        media: Vec<u8>,
+        start: Duration,
+        end: Duration,
+        user_id: u64,
+        guild_id: Option<u64>,
+    ) -> FluxResult {
+        self.edit_range("trim", media, start, end, user_id, guild_id).await
     }
 
     pub async fn uncaption(
@@ -713,7 +1076,7 @@ impl FluxHandler {
         request.operation("uncaption".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn valentine(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -721,7 +1084,32 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "valentine");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
+    }
+
+    /// Multiplies the volume of the audio track by `multiplier`.
+    pub async fn volume(&self, media: Vec<u8>, multiplier: f64, user_id: u64, guild_id: Option<u64>) -> FluxResult {
+        if !(multiplier > 0.0 && multiplier <= 10.0) {
+            bail!("The volume multiplier must be above 0 and at most 10.");
+        }
+
+        let limits = self.get_request_limits(user_id, guild_id).await?;
+
+        let mut request = FluxRequest::new_with_input_and_limits(media, &limits);
+
+        let mut options = HashMap::new();
+        options.insert("multiplier".to_owned(), multiplier.to_string());
+
+        request.operation("volume".to_owned(), options);
+        request.output();
+
+        self.run_flux_queued(request, &limits, user_id).await
+    }
+
+    /// Places several images on top of each other, from top to bottom.
+    pub async fn vstack(&self, media: Vec<Vec<u8>>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
+        self.multi_input("vstack", media, HashMap::new(), user_id, guild_id)
+            .await
     }
 
     pub async fn wormhole(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -729,7 +1117,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "wormhole");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn zoom(&self, media: Vec<u8>, user_id: u64, guild_id: Option<u64>) -> FluxResult {
@@ -737,7 +1125,7 @@ impl FluxHandler {
 
         let request = FluxRequest::new_basic(media, &limits, "zoom");
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
     }
 
     pub async fn zoom_blur(
@@ -759,6 +1147,386 @@ impl FluxHandler {
         request.operation("zoom-blur".to_owned(), options);
         request.output();
 
-        self.run_flux(request, limits.time).await
+        self.run_flux_queued(request, &limits, user_id).await
+    }
+
+    /// Runs an operation that takes all of `media` as inputs, in order. The number of inputs is
+    /// capped by the [`max_inputs`](LimitData::max_inputs) of the user.
+    async fn multi_input(
+        &self,
+        operation: &str,
+        media: Vec<Vec<u8>>,
+        options: HashMap<String, String>,
+        user_id: u64,
+        guild_id: Option<u64>,
+    ) -> FluxResult {
+        // checked before fetching limits, so that no free tier 2 request is used up for nothing
+        if media.len() < 2 {
+            bail!("This command needs at least 2 images.");
+        }
+        if media.len() as u64 > MAX_INPUTS {
+            bail!("This command can use at most {MAX_INPUTS} images.");
+        }
+
+        let limits = self.get_request_limits(user_id, guild_id).await?;
+        check_max_inputs(media.len(), &limits)?;
+
+        let mut request = FluxRequest(vec![]);
+        for input in media {
+            request.input(input);
+        }
+        request.limits(&limits);
+        request.operation(operation.to_owned(), options);
+        request.output();
+
+        self.run_flux_queued(request, &limits, user_id).await
+    }
+
+    /// Limits of a user for editing videos, which is only possible with video decoding enabled.
+    async fn video_editing_limits(&self, user_id: u64, guild_id: Option<u64>) -> anyhow::Result<LimitData> {
+        let limits = self.get_request_limits(user_id, guild_id).await?;
+        if !limits.video_decode_enabled {
+            bail!("Editing videos is only available to premium tiers (see the `patronstatus` command).");
+        }
+
+        Ok(limits)
+    }
+
+    /// Gets info about media. Probing decodes the media, so this is queued and sandboxed with
+    /// `limits` like any other job.
+    async fn media_info(&self, media: Vec<u8>, limits: &LimitData, user_id: u64) -> anyhow::Result<MediaInfo> {
+        let mut request = FluxRequest::default();
+        request.input(media);
+        request.info();
+
+        let out = self.run_flux_queued(request, limits, user_id).await?;
+        Ok(from_str::<MediaInfo>(&string_from_likely_utf8(out))?)
+    }
+
+    async fn video_info(&self, media: &[u8], limits: &LimitData, user_id: u64) -> anyhow::Result<VideoInfo> {
+        match self.media_info(media.to_vec(), limits, user_id).await? {
+            MediaInfo::Video(info) => Ok(info),
+            MediaInfo::Image(_) => bail!("This command only works on videos."),
+        }
+    }
+
+    /// Runs an operation on the part of a video between `start` and `end`, passed to Flux as the
+    /// `start_ms` and `end_ms` options. `operation` is either `trim`, to keep only that part, or
+    /// `cut`, to remove it.
+    async fn edit_range(
+        &self,
+        operation: &str,
+        media: Vec<u8>,
+        start: Duration,
+        end: Duration,
+        user_id: u64,
+        guild_id: Option<u64>,
+    ) -> FluxResult {
+        if start >= end {
+            bail!("The start time must be before the end time.");
+        }
+
+        let limits = self.video_editing_limits(user_id, guild_id).await?;
+
+        let info = self.video_info(&media, &limits, user_id).await?;
+        let length = Duration::from_millis(info.duration_ms);
+        if start >= length {
+            bail!("The video is only {} long.", format_duration(&length));
+        }
+        let end = end.min(length);
+
+        let duration = if operation == "cut" {
+            length - (end - start)
+        } else {
+            end - start
+        };
+        if duration.is_zero() {
+            bail!("Nothing would be left of the video.");
+        }
+        check_clip_limits(duration, (duration.as_secs_f64() * info.fps).ceil() as u64, &limits)?;
+
+        let mut request = FluxRequest::new_with_input_and_limits(media, &limits);
+
+        let mut options = HashMap::new();
+        options.insert("start_ms".to_owned(), start.as_millis().to_string());
+        options.insert("end_ms".to_owned(), end.as_millis().to_string());
+
+        request.operation(operation.to_owned(), options);
+        request.output();
+
+        self.run_flux_queued(request, &limits, user_id).await
+    }
+}
+
+/// Checks the number of inputs of a multi-input job against the
+/// [`max_inputs`](LimitData::max_inputs) of the user.
+fn check_max_inputs(inputs: usize, limits: &LimitData) -> anyhow::Result<()> {
+    if inputs as u64 > limits.max_inputs {
+        bail!(
+            "You can use at most {} inputs at once with your current tier. Premium tiers can use more \
+             (see the `patronstatus` command).",
+            limits.max_inputs
+        );
+    }
+
+    Ok(())
+}
+
+/// Checks the length and frame count of the result of editing a video against the limits of the
+/// user, since Flux would otherwise silently drop the frames over the limit.
+fn check_clip_limits(duration: Duration, frames: u64, limits: &LimitData) -> anyhow::Result<()> {
+    if frames > limits.frames {
+        bail!(
+            "The result would have {frames} frames, but the limit of your current tier is {}.",
+            limits.frames
+        );
+    }
+    if duration > limits.time {
+        bail!(
+            "The result would be {} long, but the limit of your current tier is {}.",
+            format_duration(&duration),
+            format_duration(&limits.time)
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, LazyLock, Mutex};
+
+    use assyst_common::metrics_handler::MetricsHandler;
+    use assyst_database::DatabaseHandler;
+
+    use super::*;
+    use crate::executor::fake::FakeExecutor;
+    use crate::flux_request::OUTPUT_FORMAT;
+    use crate::limits::{LIMITS_FREE, LIMITS_GUILD_TIER_1, LIMITS_USER_TIER_3};
+
+    const USER_ID: u64 = 1;
+
+    /// Metrics are registered globally, so they can only be created once per process.
+    static SHARED: LazyLock<(Arc<DatabaseHandler>, Arc<MetricsHandler>)> = LazyLock::new(|| {
+        let database = Arc::new(DatabaseHandler::new_lazy("postgres://localhost/assyst").unwrap());
+        let metrics = Arc::new(MetricsHandler::new(database.clone()).unwrap());
+        (database, metrics)
+    });
+
+    /// A handler with a fake executor, where [`USER_ID`] has the premium `tier`, so that their
+    /// limits never need the database.
+    fn handler(tier: u64) -> (FluxHandler, Arc<FakeExecutor>) {
+        handler_with_output(tier, b"output")
+    }
+
+    /// A [`handler`] whose fake executor answers every request with `output`.
+    fn handler_with_output(tier: u64, output: &[u8]) -> (FluxHandler, Arc<FakeExecutor>) {
+        let executor = Arc::new(FakeExecutor::new(output.to_vec()));
+        let (database, metrics) = (*SHARED).clone();
+
+        let handler = FluxHandler::new(
+            database,
+            Arc::new(Mutex::new(HashMap::from([(USER_ID, tier + 1)]))),
+            Arc::new(Mutex::new(HashMap::new())),
+            metrics,
+        )
+        .with_executor(executor.clone());
+
+        (handler, executor)
+    }
+
+    #[tokio::test]
+    async fn basic_job_applies_free_limits() {
+        let (handler, executor) = handler(0);
+
+        let output = handler.reverse(b"input".to_vec(), USER_ID, None).await.unwrap();
+        assert_eq!(output, b"output");
+
+        let requests = executor.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            requests[0].args,
+            [
+                "--page-limit",
+                "150",
+                "--res-limit",
+                "768x768",
+                "--disable-video-decode",
+                "-o",
+                "reverse"
+            ]
+        );
+        assert_eq!(requests[0].inputs, [b"input".to_vec()]);
+        assert!(requests[0].has_output);
+        assert_eq!(requests[0].time_limit, LIMITS_FREE.time);
+        assert_eq!(requests[0].sandbox, Some(LIMITS_FREE.sandbox));
+    }
+
+    #[tokio::test]
+    async fn premium_limits_enable_video_decoding() {
+        let (handler, executor) = handler(3);
+
+        handler.flip(b"input".to_vec(), USER_ID, None).await.unwrap();
+
+        let requests = executor.requests();
+        assert_eq!(
+            requests[0].args,
+            ["--page-limit", "250", "--res-limit", "4096x4096", "-o", "flip"]
+        );
+        assert_eq!(requests[0].time_limit, LIMITS_USER_TIER_3.time);
+        assert_eq!(requests[0].sandbox, Some(LIMITS_USER_TIER_3.sandbox));
+    }
+
+    #[tokio::test]
+    async fn options_are_only_passed_when_given() {
+        let (handler, executor) = handler(0);
+
+        handler.blur(b"a".to_vec(), Some(2.5), USER_ID, None).await.unwrap();
+        handler.blur(b"b".to_vec(), None, USER_ID, None).await.unwrap();
+
+        let requests = executor.requests();
+        assert_eq!(requests[0].args.last().unwrap(), "blur[strength=2.5]");
+        assert_eq!(requests[1].args.last().unwrap(), "blur");
+    }
+
+    #[tokio::test]
+    async fn chain_runs_all_steps_in_one_request() {
+        let (handler, executor) = handler(0);
+
+        let steps = crate::chain::parse_chain("caption hello | speed 2 | reverse").unwrap();
+        handler.chain(b"input".to_vec(), steps, USER_ID, None).await.unwrap();
+
+        let requests = executor.requests();
+        assert_eq!(requests.len(), 1);
+
+        let operations = requests[0]
+            .args
+            .windows(2)
+            .filter(|pair| pair[0] == "-o")
+            .map(|pair| pair[1].as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(operations, ["caption[text=hello]", "speed[multiplier=2]", "reverse"]);
+    }
+
+    #[tokio::test]
+    async fn repeated_jobs_are_cached_unless_random() {
+        let (handler, executor) = handler(0);
+
+        handler.invert(b"input".to_vec(), USER_ID, None).await.unwrap();
+        handler.invert(b"input".to_vec(), USER_ID, None).await.unwrap();
+        assert_eq!(executor.requests().len(), 1);
+
+        handler.scramble(b"input".to_vec(), USER_ID, None).await.unwrap();
+        handler.scramble(b"input".to_vec(), USER_ID, None).await.unwrap();
+        assert_eq!(executor.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn output_format_is_passed_and_cached_separately() {
+        let (handler, executor) = handler(0);
+
+        handler.invert(b"input".to_vec(), USER_ID, None).await.unwrap();
+        OUTPUT_FORMAT
+            .scope(Some(Type::WEBP), handler.invert(b"input".to_vec(), USER_ID, None))
+            .await
+            .unwrap();
+
+        let requests = executor.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(!requests[0].args.contains(&"--format".to_owned()));
+        assert_eq!(requests[1].args[requests[1].args.len() - 2..], ["--format", "webp"]);
+    }
+
+    #[tokio::test]
+    async fn multi_input_jobs_are_capped_by_tier() {
+        let (handler, executor) = handler(0);
+        let images = |n: u8| (0..n).map(|i| vec![i]).collect::<Vec<_>>();
+
+        assert!(handler.collage(images(1), USER_ID, None).await.is_err());
+        assert!(handler.collage(images(5), USER_ID, None).await.is_err());
+        assert!(executor.requests().is_empty());
+
+        handler.grid(images(3), Some(2), USER_ID, None).await.unwrap();
+        let requests = executor.requests();
+        assert_eq!(requests[0].inputs, images(3));
+        assert_eq!(requests[0].args.last().unwrap(), "grid[columns=2]");
+
+        let (handler, _) = self::handler(3);
+        handler.hstack(images(10), USER_ID, None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn video_editing_is_checked_against_limits() {
+        let info = br#"{"Video":{"file_size_bytes":1,"mime_type":"video/mp4","dimensions":"1x1","duration_ms":20000,"frame_count":600,"fps":30.0}}"#;
+        let secs = Duration::from_secs;
+
+        let (handler, executor) = handler_with_output(0, info);
+        let trim = |start, end| handler.trim(b"input".to_vec(), secs(start), secs(end), USER_ID, None);
+        assert!(trim(0, 5).await.is_err());
+        assert!(executor.requests().is_empty());
+
+        let (handler, executor) = handler_with_output(1, info);
+        let trim = |start, end| handler.trim(b"input".to_vec(), secs(start), secs(end), USER_ID, None);
+        let cut = |start, end| handler.cut(b"input".to_vec(), secs(start), secs(end), USER_ID, None);
+        assert!(trim(5, 2).await.is_err());
+        assert!(trim(25, 30).await.is_err());
+        // 10 seconds at 30 fps is over the frame limit of 200
+        assert!(trim(0, 10).await.is_err());
+        assert!(cut(0, 5).await.is_err());
+
+        trim(15, 30).await.unwrap();
+        let requests = executor.requests();
+        // probing the video is sandboxed like the job itself
+        assert_eq!(requests[0].args, ["--info"]);
+        assert_eq!(requests[0].sandbox, Some(LIMITS_GUILD_TIER_1.sandbox));
+
+        let operation = requests.last().unwrap().args.last().unwrap();
+        assert!(operation.starts_with("trim["));
+        assert!(operation.contains("start_ms=15000"));
+        assert!(operation.contains("end_ms=20000"));
+    }
+
+    #[tokio::test]
+    async fn extracted_audio_is_always_mp3() {
+        let (handler, executor) = handler(0);
+
+        OUTPUT_FORMAT
+            .scope(Some(Type::GIF), handler.extract_audio(b"input".to_vec(), USER_ID, None))
+            .await
+            .unwrap();
+
+        let requests = executor.requests();
+        assert_eq!(
+            requests[0].args[requests[0].args.len() - 4..],
+            ["-o", "extract-audio", "--format", "mp3"]
+        );
+    }
+
+    #[tokio::test]
+    async fn shrinking_stops_once_the_output_fits() {
+        use crate::shrink::ShrinkStep;
+
+        let gif = [b"GIF89a".as_slice(), &[0; 100]].concat();
+
+        let (handler, executor) = handler(0);
+        let shrunk = handler.shrink(gif.clone(), 10, USER_ID, None).await.unwrap();
+        assert_eq!(shrunk.output, b"output");
+        assert_eq!(shrunk.steps, [ShrinkStep::Rescale]);
+        assert_eq!(executor.requests().len(), 1);
+
+        // steps that don't help are skipped
+        let (handler, executor) = self::handler(0);
+        let shrunk = handler.shrink(gif.clone(), 1, USER_ID, None).await.unwrap();
+        assert_eq!(shrunk.steps, [ShrinkStep::Rescale]);
+        assert_eq!(executor.requests().len(), 2);
+
+        // and the format is kept when one was requested
+        let (handler, executor) = self::handler(0);
+        let shrunk = OUTPUT_FORMAT
+            .scope(Some(Type::GIF), handler.shrink(gif, 1, USER_ID, None))
+            .await
+            .unwrap();
+        assert_eq!(shrunk.steps, [ShrinkStep::Rescale]);
+        assert_eq!(executor.requests().len(), 1);
     }
 }
@@ -1,38 +1,42 @@
 use std::collections::HashMap;
-use std::process::Stdio;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::Duration;
 
 use anyhow::{Context, bail};
 use assyst_common::config::CONFIG;
+use assyst_common::metrics_handler::MetricsHandler;
 use assyst_common::util::process::exec_sync;
-use assyst_common::util::{hash_buffer, string_from_likely_utf8};
+use assyst_common::util::string_from_likely_utf8;
 use assyst_database::DatabaseHandler;
 use assyst_database::model::active_guild_premium_entitlement::ActiveGuildPremiumEntitlement;
 use assyst_database::model::free_tier_2_requests::FreeTier2Requests;
+use cache::FluxCache;
+use executor::{FluxExecutor, ProcessExecutor};
 use flux_request::{FluxRequest, FluxStep};
 use jobs::FluxResult;
-use libc::pid_t;
 use limits::{LIMITS_FREE, LIMITS_GUILD_TIER_1, LIMITS_USER_TIER_1, LimitData, premium_user_to_limits};
-use tokio::fs;
+use queue::{FluxQueue, QueueLimits};
+use sandbox::SandboxLimits;
 use tokio::process::Command;
-use tokio::time::timeout;
 
+pub mod archive;
+pub mod cache;
+pub mod chain;
+pub mod executor;
 pub mod flux_request;
 pub mod jobs;
 pub mod limits;
+pub mod queue;
+pub mod sandbox;
+pub mod shrink;
+pub mod workspace;
 
 const FLUX_PATH: &str = "./target/release/flux";
 const FLUX_DIR: &str = "./flux";
 const LD_LIBRARY_PATH: &str = "./build";
-
-struct FileDeletionDefer(String);
-impl Drop for FileDeletionDefer {
-    fn drop(&mut self) {
-        let _ = std::fs::remove_file(self.0.clone());
-    }
-}
+/// Time limit for getting the version of Flux.
+const VERSION_TIME_LIMIT: Duration = Duration::from_secs(10);
 
 static COMPILING: AtomicBool = AtomicBool::new(false);
 struct CompilingCompleteDefer {}
@@ -42,149 +46,142 @@ impl Drop for CompilingCompleteDefer {
     }
 }
 
+fn flux_workspace_root() -> &'static str {
+    if CONFIG.dev.flux_workspace_root_path_override.is_empty() {
+        FLUX_DIR
+    } else {
+        &CONFIG.dev.flux_workspace_root_path_override
+    }
+}
+
+/// A command that runs the Flux executable, without any arguments.
+fn flux_command() -> Command {
+    let mut command = Command::new(FLUX_PATH);
+    command.current_dir(flux_workspace_root());
+    command.env("LD_LIBRARY_PATH", LD_LIBRARY_PATH);
+    command
+}
+
+/// Flux arguments of a step. Inputs and outputs are passed differently depending on how Flux is
+/// ran, so have none.
+fn step_args(step: &FluxStep) -> Vec<String> {
+    match step {
+        FluxStep::Input(_) | FluxStep::Output => vec![],
+        FluxStep::Operation((operation, options)) => {
+            let mut op_full = operation.clone();
+
+            if !options.is_empty() {
+                op_full += "[";
+                for op in options {
+                    op_full += op.0;
+                    op_full += "=";
+                    op_full += &op.1.replace(';', "\\;");
+                    op_full += ";";
+                }
+                // remove trailing ";"
+                op_full = op_full[..op_full.len() - 1].to_owned();
+
+                op_full += "]";
+            }
+
+            vec!["-o".to_owned(), op_full]
+        },
+        FluxStep::OutputFormat(format) => vec!["--format".to_owned(), format.as_str().to_owned()],
+        FluxStep::ImagePageLimit(l) => vec!["--page-limit".to_owned(), l.to_string()],
+        FluxStep::ResolutionLimit((w, h)) => vec!["--res-limit".to_owned(), format!("{w}x{h}")],
+        FluxStep::VideoDecodeDisabled => vec!["--disable-video-decode".to_owned()],
+        FluxStep::Info => vec!["--info".to_owned()],
+        FluxStep::Version => vec!["--version".to_owned()],
+    }
+}
+
 pub struct FluxHandler {
     database_handler: Arc<DatabaseHandler>,
     premium_users: Arc<Mutex<HashMap<u64, u64>>>,
     premium_guilds: Arc<Mutex<HashMap<i64, ActiveGuildPremiumEntitlement>>>,
+    metrics_handler: Arc<MetricsHandler>,
+    queue: FluxQueue,
+    cache: FluxCache,
+    executor: Arc<dyn FluxExecutor>,
 }
 impl FluxHandler {
     pub fn new(
         database_handler: Arc<DatabaseHandler>,
         premium_users: Arc<Mutex<HashMap<u64, u64>>>,
         premium_guilds: Arc<Mutex<HashMap<i64, ActiveGuildPremiumEntitlement>>>,
+        metrics_handler: Arc<MetricsHandler>,
     ) -> Self {
         Self {
             database_handler,
             premium_users,
             premium_guilds,
+            metrics_handler,
+            queue: FluxQueue::default(),
+            cache: FluxCache::new(),
+            executor: Arc::new(ProcessExecutor),
         }
     }
 
+    /// Replaces the executor that runs requests, for example with one that never runs Flux in
+    /// tests.
+    #[must_use]
+    pub fn with_executor(mut self, executor: Arc<dyn FluxExecutor>) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    /// Replaces the limits of the job queue, which are [`QueueLimits::default`] otherwise.
+    #[must_use]
+    pub fn with_queue_limits(mut self, limits: QueueLimits) -> Self {
+        self.queue = FluxQueue::new(limits);
+        self
+    }
+
     pub fn set_premium_users(&self, users: HashMap<u64, u64>) {
         *self.premium_users.lock().unwrap() = users;
     }
 
-    pub async fn run_flux(&self, request: FluxRequest, time_limit: Duration) -> FluxResult {
-        let mut input_file_paths: Vec<String> = vec![];
-        let mut output_file_path: String = String::new();
-        let mut args: Vec<String> = vec![];
-
-        for step in request.0 {
-            match step {
-                FluxStep::Input(i) => {
-                    let hash = hash_buffer(&i);
-                    let path = format!("/tmp/{hash}");
-                    fs::write(&path, &i).await.context("failed to write input file")?;
-                    input_file_paths.push(path.clone());
-
-                    args.push("-i".to_owned());
-                    args.push(path);
-                },
-                FluxStep::Operation((operation, options)) => {
-                    let mut op_full = operation;
-
-                    if !options.is_empty() {
-                        op_full += "[";
-                        for op in &options {
-                            op_full += op.0;
-                            op_full += "=";
-                            op_full += &op.1.replace(';', "\\;");
-                            op_full += ";";
-                        }
-                        // remove trailing ";"
-                        op_full = op_full[..op_full.len() - 1].to_owned();
-
-                        op_full += "]";
-                    }
-
-                    args.push("-o".to_owned());
-                    args.push(op_full);
-                },
-                FluxStep::Output => {
-                    let unix = SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .expect("time went backwards")
-                        .as_millis();
-
-                    let path = format!("/tmp/{unix}");
-                    args.push(path.clone());
-                    output_file_path = path;
-                },
-                FluxStep::ImagePageLimit(l) => {
-                    args.push("--page-limit".to_owned());
-                    args.push(l.to_string());
-                },
-                FluxStep::ResolutionLimit((w, h)) => {
-                    args.push("--res-limit".to_owned());
-                    args.push(format!("{w}x{h}"));
-                },
-                FluxStep::VideoDecodeDisabled => {
-                    args.push("--disable-video-decode".to_owned());
-                },
-                FluxStep::Info => {
-                    args.push("--info".to_owned());
-                },
-                FluxStep::Version => args.push("--version".to_owned()),
+    /// Waits for a free slot in the job queue, at the priority of `limits`, and then runs the
+    /// request.
+    ///
+    /// If the same request was ran recently, its cached output is returned instead, without
+    /// queueing at all.
+    pub async fn run_flux_queued(&self, request: FluxRequest, limits: &LimitData, user_id: u64) -> FluxResult {
+        let cache_key = FluxCache::key(&request);
+        if let Some(key) = &cache_key {
+            if let Some(output) = self.cache.get(key) {
+                self.metrics_handler.add_flux_cache_hit();
+                return Ok(output);
             }
+            self.metrics_handler.add_flux_cache_miss();
         }
 
-        // defer file deletions to func return
-        let mut defers: Vec<FileDeletionDefer> = vec![];
-        for input in input_file_paths {
-            defers.push(FileDeletionDefer(input.clone()));
-        }
-        defers.push(FileDeletionDefer(output_file_path.clone()));
-
-        let flux_workspace_root = if CONFIG.dev.flux_workspace_root_path_override.is_empty() {
-            FLUX_DIR.to_owned()
-        } else {
-            CONFIG.dev.flux_workspace_root_path_override.clone()
+        let output = {
+            let _ticket = self.queue.acquire(user_id, limits.priority).await?;
+            self.run_flux(request, limits.time, Some(&limits.sandbox)).await?
         };
 
-        if COMPILING.load(Ordering::Relaxed) {
-            bail!("The image service is still preparing. Try again in a few seconds.");
-        }
-
-        let mut command = Command::new(FLUX_PATH);
-        command.args(args);
-        command.current_dir(flux_workspace_root);
-        command.env("LD_LIBRARY_PATH", LD_LIBRARY_PATH);
-        command.stdout(Stdio::piped());
-        command.stderr(Stdio::piped());
-        let spawn = command.spawn().context("Failed to execute flux")?;
-        let id = spawn.id();
-        let output = timeout(time_limit, spawn.wait_with_output()).await;
-
-        let output = if let Ok(o) = output {
-            o
-        } else {
-            // send SIGTERM to flux to clean up child processes
-            if let Some(id) = id {
-                unsafe { libc::kill(id as pid_t, libc::SIGTERM) };
-            };
-            bail!("The operation timed out");
-        }
-        .context("Failed to execute flux")?;
-
-        if !output.status.success() {
-            bail!(
-                "{} ({})",
-                string_from_likely_utf8(output.stderr).trim(),
-                output.status.to_string()
-            );
+        if let Some(key) = cache_key {
+            self.cache.insert(key, &output);
+            self.metrics_handler
+                .update_cache_size("flux_outputs", self.cache.entry_count() as usize);
         }
 
-        let output = if !output_file_path.is_empty() {
-            fs::read(&output_file_path)
-                .await
-                .context("Failed to read output file")?
-        } else {
-            output.stdout
-        };
-
         Ok(output)
     }
 
+    /// Runs a Flux request with the executor of this handler, killing Flux after `time_limit`. If
+    /// `sandbox` is given, Flux is also limited to those resources, and exceeding them is reported
+    /// as a [`SandboxViolation`](sandbox::SandboxViolation).
+    pub async fn run_flux(
+        &self,
+        request: FluxRequest,
+        time_limit: Duration,
+        sandbox: Option<&SandboxLimits>,
+    ) -> FluxResult {
+        self.executor.execute(request, time_limit, sandbox).await
+    }
+
     pub async fn compile_flux() -> anyhow::Result<()> {
         const CARGO_EXIT_FAIL: i32 = 101;
         COMPILING.fetch_or(true, Ordering::Relaxed);
@@ -192,11 +189,7 @@ impl FluxHandler {
 
         let res = exec_sync(&format!(
             "cd {} && rm -f {FLUX_PATH} && mold -run ~/.cargo/bin/cargo build -q --release",
-            if CONFIG.dev.flux_workspace_root_path_override.is_empty() {
-                FLUX_DIR
-            } else {
-                &CONFIG.dev.flux_workspace_root_path_override
-            }
+            flux_workspace_root()
         ))
         .context("Failed to compile flux")?;
 
@@ -210,6 +203,21 @@ impl FluxHandler {
     /// This function will remove a free voter request if the user has any
     /// and are not a patron!
     pub async fn get_request_limits(&self, user_id: u64, guild_id: Option<u64>) -> Result<LimitData, anyhow::Error> {
+        self.request_limits(user_id, guild_id, true).await
+    }
+
+    /// Same as [`Self::get_request_limits`], but without using up a free voter request. For work
+    /// done on behalf of a request whose limits were already paid for.
+    pub async fn peek_request_limits(&self, user_id: u64, guild_id: Option<u64>) -> Result<LimitData, anyhow::Error> {
+        self.request_limits(user_id, guild_id, false).await
+    }
+
+    async fn request_limits(
+        &self,
+        user_id: u64,
+        guild_id: Option<u64>,
+        consume_free_tier_2: bool,
+    ) -> Result<LimitData, anyhow::Error> {
         if let Some(p) = {
             let premium_users = self.premium_users.lock().unwrap();
             premium_users.get(&user_id).copied()
@@ -231,9 +239,11 @@ impl FluxHandler {
         let user_tier2 = FreeTier2Requests::get_user_free_tier_2_requests(&self.database_handler, user_id).await?;
 
         if user_tier2.count > 0 {
-            user_tier2
-                .change_free_tier_2_requests(&self.database_handler, -1)
-                .await?;
+            if consume_free_tier_2 {
+                user_tier2
+                    .change_free_tier_2_requests(&self.database_handler, -1)
+                    .await?;
+            }
             Ok(LIMITS_USER_TIER_1)
         } else {
             Ok(LIMITS_FREE)
@@ -243,6 +253,10 @@ impl FluxHandler {
     pub async fn get_version(&self) -> anyhow::Result<String> {
         let mut req = FluxRequest::default();
         req.version();
-        Ok(string_from_likely_utf8(self.run_flux(req, Duration::MAX).await?))
+
+        let out = self
+            .run_flux(req, VERSION_TIME_LIMIT, Some(&LIMITS_FREE.sandbox))
+            .await?;
+        Ok(string_from_likely_utf8(out))
     }
 }
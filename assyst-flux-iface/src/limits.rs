@@ -1,10 +1,24 @@
 use std::time::Duration;
 
+use crate::sandbox::SandboxLimits;
+
+const MIB: u64 = 1024 * 1024;
+const GIB: u64 = 1024 * MIB;
+
+/// Maximum number of inputs of a multi-input job on any tier.
+pub const MAX_INPUTS: u64 = 10;
+
 pub struct LimitData {
     pub time: Duration,
     pub size: u64,
     pub frames: u64,
     pub video_decode_enabled: bool,
+    /// Priority of jobs in the Flux queue. Higher priorities are ran first.
+    pub priority: u8,
+    /// Resource limits of the Flux process.
+    pub sandbox: SandboxLimits,
+    /// Maximum number of inputs of a multi-input job, such as a collage.
+    pub max_inputs: u64,
 }
 
 pub const LIMITS_FREE: LimitData = LimitData {
@@ -12,6 +26,14 @@ pub const LIMITS_FREE: LimitData = LimitData {
     size: 768,
     frames: 150,
     video_decode_enabled: false,
+    priority: 0,
+    sandbox: SandboxLimits {
+        memory_bytes: GIB,
+        cpu_time: Duration::from_secs(60),
+        file_size_bytes: 64 * MIB,
+        processes: 16,
+    },
+    max_inputs: 4,
 };
 
 pub const LIMITS_USER_TIER_1: LimitData = LimitData {
@@ -19,6 +41,14 @@ pub const LIMITS_USER_TIER_1: LimitData = LimitData {
     size: 1024,
     frames: 200,
     video_decode_enabled: true,
+    priority: 1,
+    sandbox: SandboxLimits {
+        memory_bytes: 2 * GIB,
+        cpu_time: Duration::from_secs(90),
+        file_size_bytes: 128 * MIB,
+        processes: 32,
+    },
+    max_inputs: 6,
 };
 
 pub const LIMITS_USER_TIER_2: LimitData = LimitData {
@@ -26,6 +56,14 @@ pub const LIMITS_USER_TIER_2: LimitData = LimitData {
     size: 2048,
     frames: 225,
     video_decode_enabled: true,
+    priority: 2,
+    sandbox: SandboxLimits {
+        memory_bytes: 3 * GIB,
+        cpu_time: Duration::from_secs(120),
+        file_size_bytes: 256 * MIB,
+        processes: 32,
+    },
+    max_inputs: 8,
 };
 
 pub const LIMITS_USER_TIER_3: LimitData = LimitData {
@@ -33,6 +71,14 @@ pub const LIMITS_USER_TIER_3: LimitData = LimitData {
     size: 4096,
     frames: 250,
     video_decode_enabled: true,
+    priority: 3,
+    sandbox: SandboxLimits {
+        memory_bytes: 4 * GIB,
+        cpu_time: Duration::from_secs(180),
+        file_size_bytes: 512 * MIB,
+        processes: 64,
+    },
+    max_inputs: MAX_INPUTS,
 };
 
 pub const LIMITS_GUILD_TIER_1: LimitData = LimitData {
@@ -40,6 +86,14 @@ pub const LIMITS_GUILD_TIER_1: LimitData = LimitData {
     size: 1024,
     frames: 200,
     video_decode_enabled: true,
+    priority: 1,
+    sandbox: SandboxLimits {
+        memory_bytes: 2 * GIB,
+        cpu_time: Duration::from_secs(90),
+        file_size_bytes: 128 * MIB,
+        processes: 32,
+    },
+    max_inputs: 6,
 };
 
 #[must_use]
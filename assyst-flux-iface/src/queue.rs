@@ -0,0 +1,218 @@
+use std::cmp::Reverse;
+use std::sync::Mutex;
+
+use anyhow::bail;
+use assyst_common::config::CONFIG;
+use tokio::sync::{Notify, mpsc, oneshot};
+
+/// Default maximum number of Flux processes running at once.
+pub const DEFAULT_MAX_CONCURRENT_JOBS: usize = 4;
+/// Default maximum number of jobs waiting for a free slot.
+pub const DEFAULT_MAX_QUEUED_JOBS: usize = 100;
+/// Default maximum number of jobs a single user can have queued or running at once.
+pub const DEFAULT_MAX_JOBS_PER_USER: usize = 2;
+
+/// How many jobs a [`FluxQueue`] runs and holds at once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueueLimits {
+    /// Maximum number of Flux processes running at once
+    pub concurrent_jobs: usize,
+    /// Maximum number of jobs waiting for a free slot
+    pub queued_jobs: usize,
+    /// Maximum number of jobs a single user can have queued or running at once
+    pub jobs_per_user: usize,
+}
+impl QueueLimits {
+    /// Limits configured by `dev.flux_max_concurrent_jobs`, `dev.flux_max_queued_jobs` and
+    /// `dev.flux_max_jobs_per_user`, using the default for any that are not set.
+    #[must_use]
+    pub fn from_config() -> Self {
+        Self {
+            concurrent_jobs: CONFIG
+                .dev
+                .flux_max_concurrent_jobs
+                .unwrap_or(DEFAULT_MAX_CONCURRENT_JOBS),
+            queued_jobs: CONFIG.dev.flux_max_queued_jobs.unwrap_or(DEFAULT_MAX_QUEUED_JOBS),
+            jobs_per_user: CONFIG.dev.flux_max_jobs_per_user.unwrap_or(DEFAULT_MAX_JOBS_PER_USER),
+        }
+    }
+}
+impl Default for QueueLimits {
+    fn default() -> Self {
+        Self {
+            concurrent_jobs: DEFAULT_MAX_CONCURRENT_JOBS,
+            queued_jobs: DEFAULT_MAX_QUEUED_JOBS,
+            jobs_per_user: DEFAULT_MAX_JOBS_PER_USER,
+        }
+    }
+}
+
+/// A queue position of a job that is waiting to run.
+pub struct QueuePosition {
+    /// 1-based position of the job in the queue
+    pub position: usize,
+    /// Should be sent to once the position has been shown to the user. The job does not start
+    /// running until the last position it reported has been shown, so that the output of the job
+    /// is never overwritten by a stale position.
+    pub shown: oneshot::Sender<()>,
+}
+
+tokio::task_local! {
+    /// Receives the queue positions of all jobs queued by the current task, if set.
+    pub static QUEUE_POSITION_UPDATES: mpsc::UnboundedSender<QueuePosition>;
+}
+
+struct QueuedJob {
+    id: u64,
+    user_id: u64,
+    priority: u8,
+}
+
+#[derive(Default)]
+struct QueueState {
+    /// Jobs waiting for a free slot, in the order they were queued
+    waiting: Vec<QueuedJob>,
+    /// Users of all running jobs, one entry per job
+    running: Vec<u64>,
+    next_id: u64,
+}
+impl QueueState {
+    fn running_for(&self, user_id: u64) -> usize {
+        self.running.iter().filter(|&&u| u == user_id).count()
+    }
+
+    /// The waiting jobs, in the order they will run: highest priority first, then jobs of users
+    /// with the fewest running jobs, then first come, first served.
+    fn ordered(&self) -> Vec<&QueuedJob> {
+        let mut ordered = self.waiting.iter().collect::<Vec<_>>();
+        ordered.sort_by_key(|job| (Reverse(job.priority), self.running_for(job.user_id), job.id));
+        ordered
+    }
+}
+
+/// Schedules Flux jobs so that at most [`QueueLimits::concurrent_jobs`] processes run at once.
+#[derive(Default)]
+pub struct FluxQueue {
+    limits: QueueLimits,
+    state: Mutex<QueueState>,
+    /// Notified whenever a job is queued, started or finished
+    changed: Notify,
+}
+impl FluxQueue {
+    #[must_use]
+    pub fn new(limits: QueueLimits) -> Self {
+        Self {
+            limits,
+            ..Self::default()
+        }
+    }
+
+    /// Queues a job and waits until it is its turn to run. The job holds its slot until the returned
+    /// ticket is dropped.
+    ///
+    /// While waiting, the position of the job is sent to [`QUEUE_POSITION_UPDATES`] whenever it
+    /// changes.
+    pub async fn acquire(&self, user_id: u64, priority: u8) -> anyhow::Result<QueueTicket<'_>> {
+        let mut ticket = {
+            let mut state = self.state.lock().unwrap();
+
+            let user_jobs = state.running_for(user_id) + state.waiting.iter().filter(|j| j.user_id == user_id).count();
+            if user_jobs >= self.limits.jobs_per_user {
+                bail!("You already have {user_jobs} image jobs running or queued. Wait for them to finish first.");
+            }
+            if state.waiting.len() >= self.limits.queued_jobs {
+                bail!("The image service is too busy right now. Try again in a few minutes.");
+            }
+
+            let id = state.next_id;
+            state.next_id += 1;
+            state.waiting.push(QueuedJob { id, user_id, priority });
+
+            QueueTicket {
+                queue: self,
+                id,
+                user_id,
+                running: false,
+            }
+        };
+
+        let mut last_position = None;
+        let mut last_shown = None;
+
+        loop {
+            // register interest before checking, so that a change in between is not missed
+            let changed = self.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+
+            let position = {
+                let mut state = self.state.lock().unwrap();
+                let position = state
+                    .ordered()
+                    .iter()
+                    .position(|job| job.id == ticket.id)
+                    .expect("queued job is not in the queue")
+                    + 1;
+
+                if position == 1 && state.running.len() < self.limits.concurrent_jobs {
+                    state.waiting.retain(|job| job.id != ticket.id);
+                    state.running.push(user_id);
+                    ticket.running = true;
+                    None
+                } else {
+                    Some(position)
+                }
+            };
+
+            let Some(position) = position else {
+                // other jobs may be able to move up now
+                self.changed.notify_waiters();
+                break;
+            };
+
+            if last_position != Some(position) {
+                last_position = Some(position);
+                last_shown = QUEUE_POSITION_UPDATES
+                    .try_with(|updates| {
+                        let (shown, rx) = oneshot::channel();
+                        updates.send(QueuePosition { position, shown }).ok().map(|()| rx)
+                    })
+                    .ok()
+                    .flatten();
+            }
+
+            changed.await;
+        }
+
+        if let Some(shown) = last_shown {
+            let _ = shown.await;
+        }
+
+        Ok(ticket)
+    }
+}
+
+/// A job in the [`FluxQueue`]. Dropping it removes the job from the queue, or frees its slot if it
+/// is running.
+pub struct QueueTicket<'a> {
+    queue: &'a FluxQueue,
+    id: u64,
+    user_id: u64,
+    running: bool,
+}
+impl Drop for QueueTicket<'_> {
+    fn drop(&mut self) {
+        {
+            let mut state = self.queue.state.lock().unwrap();
+            if self.running {
+                if let Some(index) = state.running.iter().position(|&u| u == self.user_id) {
+                    state.running.swap_remove(index);
+                }
+            } else {
+                state.waiting.retain(|job| job.id != self.id);
+            }
+        }
+
+        self.queue.changed.notify_waiters();
+    }
+}
@@ -0,0 +1,216 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::fd::{AsRawFd, RawFd};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use assyst_common::config::CONFIG;
+use tokio::process::Command;
+
+/// Resource limits of a single Flux process, and everything it spawns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SandboxLimits {
+    /// Maximum address space of each process (`RLIMIT_AS`), and maximum memory of all processes
+    /// together when cgroups are used
+    pub memory_bytes: u64,
+    /// Maximum CPU time of each process (`RLIMIT_CPU`)
+    pub cpu_time: Duration,
+    /// Maximum size of any file written (`RLIMIT_FSIZE`)
+    pub file_size_bytes: u64,
+    /// Maximum number of processes and threads, only enforced when cgroups are used
+    pub processes: u64,
+}
+
+/// Extra CPU time after the soft limit (and `SIGXCPU`) before the process is killed outright.
+const CPU_TIME_GRACE_SECS: u64 = 5;
+
+/// A limit that a Flux process was stopped for exceeding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SandboxViolation {
+    Memory,
+    CpuTime,
+    FileSize,
+    Processes,
+}
+impl SandboxViolation {
+    /// Works out which limit, if any, a failed Flux process exceeded.
+    #[must_use]
+    pub fn detect(status: ExitStatus, stderr: &str, cgroup: Option<&Cgroup>) -> Option<Self> {
+        if let Some(violation) = cgroup.and_then(Cgroup::violation) {
+            return Some(violation);
+        }
+
+        match status.signal() {
+            Some(libc::SIGXCPU) => Some(Self::CpuTime),
+            Some(libc::SIGXFSZ) => Some(Self::FileSize),
+            _ if stderr.contains("memory allocation of") || stderr.contains("Cannot allocate memory") => {
+                Some(Self::Memory)
+            },
+            _ => None,
+        }
+    }
+
+    /// User-facing description of the violation.
+    #[must_use]
+    pub fn message(self, limits: &SandboxLimits) -> String {
+        match self {
+            Self::Memory => format!(
+                "The operation ran out of memory (limit: {} MiB). Try a smaller input.",
+                limits.memory_bytes / 1024 / 1024
+            ),
+            Self::CpuTime => format!(
+                "The operation used too much processing time (limit: {} seconds). Try a smaller input.",
+                limits.cpu_time.as_secs()
+            ),
+            Self::FileSize => format!(
+                "The output was too large (limit: {} MiB).",
+                limits.file_size_bytes / 1024 / 1024
+            ),
+            Self::Processes => "The operation tried to start too many processes.".to_owned(),
+        }
+    }
+}
+
+/// Applies `limits` to the process spawned by `command`, and moves it into `cgroup`, if any.
+pub fn apply(command: &mut Command, limits: &SandboxLimits, cgroup: Option<&Cgroup>) {
+    let memory = limits.memory_bytes;
+    let cpu = limits.cpu_time.as_secs().max(1);
+    let file_size = limits.file_size_bytes;
+    let procs_fd = cgroup.map(Cgroup::procs_fd);
+
+    // SAFETY: the closure only makes async-signal-safe syscalls and does not allocate.
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(fd) = procs_fd {
+                // writing 0 moves the writing process into the cgroup
+                if libc::write(fd, b"0".as_ptr().cast(), 1) != 1 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+
+            for (resource, soft, hard) in [
+                (libc::RLIMIT_AS, memory, memory),
+                (libc::RLIMIT_CPU, cpu, cpu + CPU_TIME_GRACE_SECS),
+                (libc::RLIMIT_FSIZE, file_size, file_size),
+                (libc::RLIMIT_CORE, 0, 0),
+            ] {
+                let limit = libc::rlimit {
+                    rlim_cur: soft,
+                    rlim_max: hard,
+                };
+                if libc::setrlimit(resource, &limit) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+
+            Ok(())
+        });
+    }
+}
+
+static NEXT_CGROUP_ID: AtomicU64 = AtomicU64::new(0);
+
+/// How long to wait for the processes in a cgroup to exit after killing them, before removing it.
+const KILL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A cgroup v2 for a single Flux process, created under `dev.flux_cgroup_root`. The root needs to
+/// be delegated to the user Assyst runs as, with the memory and pids controllers enabled.
+///
+/// Removed, killing anything left inside of it, when dropped. Waiting for the processes to exit
+/// blocks, so is done on the blocking thread pool.
+pub struct Cgroup {
+    path: PathBuf,
+    procs: File,
+}
+impl Cgroup {
+    /// Creates a cgroup with `limits` if cgroups are configured.
+    pub async fn create(limits: &SandboxLimits) -> anyhow::Result<Option<Self>> {
+        let Some(root) = CONFIG.dev.flux_cgroup_root.as_deref().filter(|r| !r.is_empty()) else {
+            return Ok(None);
+        };
+
+        let path = PathBuf::from(root).join(format!(
+            "flux-{}-{}",
+            std::process::id(),
+            NEXT_CGROUP_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let limits = *limits;
+
+        tokio::task::spawn_blocking(move || Self::create_blocking(path, &limits).map(Some)).await?
+    }
+
+    fn create_blocking(path: PathBuf, limits: &SandboxLimits) -> anyhow::Result<Self> {
+        fs::create_dir(&path).context("Failed to create flux cgroup")?;
+
+        // construct before writing the limits, so that the cgroup is removed if they fail
+        let procs = OpenOptions::new()
+            .write(true)
+            .open(path.join("cgroup.procs"))
+            .context("Failed to open flux cgroup")?;
+        let cgroup = Self { path, procs };
+
+        fs::write(cgroup.path.join("memory.max"), limits.memory_bytes.to_string())
+            .context("Failed to set flux cgroup memory limit")?;
+        fs::write(cgroup.path.join("pids.max"), limits.processes.to_string())
+            .context("Failed to set flux cgroup process limit")?;
+        // not every system has swap accounting
+        let _ = fs::write(cgroup.path.join("memory.swap.max"), "0");
+
+        Ok(cgroup)
+    }
+
+    fn procs_fd(&self) -> RawFd {
+        self.procs.as_raw_fd()
+    }
+
+    fn violation(&self) -> Option<SandboxViolation> {
+        if event_count(&self.path, "memory.events", "oom_kill") > 0 {
+            Some(SandboxViolation::Memory)
+        } else if event_count(&self.path, "pids.events", "max") > 0 {
+            Some(SandboxViolation::Processes)
+        } else {
+            None
+        }
+    }
+}
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        let path = std::mem::take(&mut self.path);
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => drop(runtime.spawn_blocking(move || remove_cgroup(&path))),
+            Err(_) => remove_cgroup(&path),
+        }
+    }
+}
+
+/// Reads a counter out of a flat-keyed cgroup file, like `memory.events`.
+fn event_count(cgroup: &Path, file: &str, key: &str) -> u64 {
+    fs::read_to_string(cgroup.join(file))
+        .ok()
+        .and_then(|events| {
+            events
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix(' ')?.trim().parse().ok())
+        })
+        .unwrap_or(0)
+}
+
+/// Kills everything left in a cgroup and removes it. Blocks for up to [`KILL_TIMEOUT`].
+fn remove_cgroup(cgroup: &Path) {
+    if let Ok(mut kill) = OpenOptions::new().write(true).open(cgroup.join("cgroup.kill")) {
+        let _ = kill.write_all(b"1");
+    }
+
+    // killing is asynchronous, and the cgroup can't be removed until all of its processes exit
+    let start = Instant::now();
+    while event_count(cgroup, "cgroup.events", "populated") > 0 && start.elapsed() < KILL_TIMEOUT {
+        std::thread::sleep(Duration::from_millis(5));
+    }
+
+    let _ = fs::remove_dir(cgroup);
+}
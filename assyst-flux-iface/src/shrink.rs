@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use anyhow::bail;
+use assyst_common::util::filetype::{Type, get_sig};
+use tracing::debug;
+
+use crate::FluxHandler;
+use crate::flux_request::{FluxRequest, requested_output_format};
+
+/// A change made to an output to make it smaller, see [`FluxHandler::shrink`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShrinkStep {
+    /// Halves the width and height
+    Rescale,
+    /// Re-encodes a GIF as a WebM video
+    ReencodeWebm,
+}
+impl ShrinkStep {
+    /// Steps that can be applied to `format`, least noticeable first.
+    fn steps_for(format: Type) -> &'static [Self] {
+        match format {
+            Type::GIF => &[Self::Rescale, Self::ReencodeWebm],
+            Type::MP4 | Type::WEBM | Type::PNG | Type::JPEG | Type::WEBP => &[Self::Rescale],
+            Type::MP3 | Type::ZIP => &[],
+        }
+    }
+
+    /// Adds this step to `request`, with its output in `format` unless the step changes it.
+    fn apply(self, request: &mut FluxRequest, format: Type) {
+        match self {
+            Self::Rescale => {
+                let mut options = HashMap::new();
+                options.insert("scale".to_owned(), "0.5".to_owned());
+
+                request.operation("resize".to_owned(), options);
+                request.output_as(format);
+            },
+            Self::ReencodeWebm => request.output_as(Type::WEBM),
+        }
+    }
+
+    /// User-facing description of the step.
+    #[must_use]
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Rescale => "half resolution",
+            Self::ReencodeWebm => "converted to webm",
+        }
+    }
+}
+
+/// Result of [`FluxHandler::shrink`].
+pub struct ShrunkOutput {
+    pub output: Vec<u8>,
+    /// Steps that were applied to the output, in order
+    pub steps: Vec<ShrinkStep>,
+}
+
+impl FluxHandler {
+    /// Tries to make `media` fit in `max_bytes`, by applying [`ShrinkStep`]s one after another
+    /// until it does. Steps that fail or do not make the output any smaller are skipped.
+    ///
+    /// Returns the smallest output, which may still not fit if all steps were applied. GIFs are
+    /// only re-encoded as videos when no output format was requested by the user.
+    pub async fn shrink(
+        &self,
+        media: Vec<u8>,
+        max_bytes: u64,
+        user_id: u64,
+        guild_id: Option<u64>,
+    ) -> anyhow::Result<ShrunkOutput> {
+        let Some(format) = get_sig(&media) else {
+            bail!("Unsupported output format");
+        };
+
+        // the output was already paid for by the request that made it
+        let limits = self.peek_request_limits(user_id, guild_id).await?;
+        let keep_format = requested_output_format().is_some();
+
+        let mut shrunk = ShrunkOutput {
+            output: media,
+            steps: vec![],
+        };
+
+        for &step in ShrinkStep::steps_for(format) {
+            if shrunk.output.len() as u64 <= max_bytes {
+                break;
+            }
+            if step == ShrinkStep::ReencodeWebm && keep_format {
+                continue;
+            }
+
+            let mut request = FluxRequest::new_with_input_and_limits(shrunk.output.clone(), &limits);
+            step.apply(&mut request, format);
+
+            // a step that fails, such as one that needs to decode a video on the free tier, is
+            // skipped like one that doesn't help
+            let output = match self.run_flux_queued(request, &limits, user_id).await {
+                Ok(output) => output,
+                Err(e) => {
+                    debug!("Shrink step {step:?} failed, skipping it: {e:#}");
+                    continue;
+                },
+            };
+            if output.len() < shrunk.output.len() {
+                shrunk.output = output;
+                shrunk.steps.push(step);
+            }
+        }
+
+        Ok(shrunk)
+    }
+}
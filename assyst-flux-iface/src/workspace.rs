@@ -0,0 +1,127 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::Context;
+use assyst_common::config::CONFIG;
+use assyst_common::util::unix_timestamp;
+use libc::pid_t;
+use tokio::fs::{self, DirBuilder};
+
+const DEFAULT_WORKSPACE_DIR: &str = "assyst-flux";
+
+static NEXT_WORKSPACE_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Prefix of the names of everything this process creates in the [`workspace_root`]: its process ID
+/// and the time it started. The process ID alone isn't enough to tell files of this process apart
+/// from ones left behind by a crashed one, as Assyst often restarts with the same ID in containers.
+static RUN_PREFIX: LazyLock<String> = LazyLock::new(|| format!("{}-{}", std::process::id(), unix_timestamp()));
+
+/// Directory that all job workspaces are created in. Configured by `dev.flux_temp_root`, or a
+/// directory in the system temporary directory by default.
+#[must_use]
+pub fn workspace_root() -> PathBuf {
+    match CONFIG.dev.flux_temp_root.as_deref() {
+        Some(root) if !root.is_empty() => PathBuf::from(root),
+        _ => std::env::temp_dir().join(DEFAULT_WORKSPACE_DIR),
+    }
+}
+
+/// A private directory for the input and output files of a single Flux job.
+///
+/// Named after the [`RUN_PREFIX`] of this process and a per-process counter, so names never collide
+/// between jobs, and workspaces left behind by a crashed process can be told apart from live ones.
+/// The directory and everything in it is removed when dropped.
+pub struct JobWorkspace {
+    path: PathBuf,
+    inputs: usize,
+}
+impl JobWorkspace {
+    pub async fn create() -> anyhow::Result<Self> {
+        let root = workspace_root();
+        fs::create_dir_all(&root)
+            .await
+            .context("Failed to create flux workspace root")?;
+
+        let path = root.join(format!(
+            "{}-{}",
+            *RUN_PREFIX,
+            NEXT_WORKSPACE_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        // only readable by us, since inputs are user uploads
+        DirBuilder::new()
+            .mode(0o700)
+            .create(&path)
+            .await
+            .context("Failed to create flux workspace")?;
+
+        Ok(Self { path, inputs: 0 })
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes an input file into the workspace, returning its path.
+    pub async fn write_input(&mut self, input: &[u8]) -> anyhow::Result<PathBuf> {
+        let path = self.path.join(format!("input-{}", self.inputs));
+        self.inputs += 1;
+
+        fs::write(&path, input).await.context("Failed to write input file")?;
+        Ok(path)
+    }
+
+    /// Path that the output of the job should be written to.
+    #[must_use]
+    pub fn output_path(&self) -> PathBuf {
+        self.path.join("output")
+    }
+
+    /// Removes workspaces left behind by Assyst processes that are no longer
+    /// running, for example after a crash. Should be called on startup.
+    ///
+    /// Anything with the process ID of this process, but not its [`RUN_PREFIX`], was left behind by
+    /// an earlier process with the same ID.
+    pub async fn remove_stale() -> anyhow::Result<usize> {
+        let mut entries = match fs::read_dir(workspace_root()).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e).context("Failed to read flux workspace root"),
+        };
+
+        let mut removed = 0;
+        let own_prefix = format!("{}-", *RUN_PREFIX);
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            let Some(name) = name.to_str() else {
+                continue;
+            };
+            let Some(pid) = name.split_once('-').and_then(|(pid, _)| pid.parse::<pid_t>().ok()) else {
+                continue;
+            };
+
+            if name.starts_with(&own_prefix) || (pid != std::process::id() as pid_t && process_is_running(pid)) {
+                continue;
+            }
+
+            if fs::remove_dir_all(entry.path()).await.is_ok() {
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+}
+impl Drop for JobWorkspace {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+fn process_is_running(pid: pid_t) -> bool {
+    // signal 0 only checks whether the process exists. EPERM means it exists, but is not ours
+    unsafe { libc::kill(pid, 0) == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM) }
+}
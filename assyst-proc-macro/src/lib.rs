@@ -205,6 +205,9 @@ pub fn command(attrs: TokenStream, func: TokenStream) -> TokenStream {
 
     let flag_descriptions = fields.remove("flag_descriptions").unwrap_or_else(empty_array_expr);
     let guild_only = fields.remove("guild_only").unwrap_or_else(false_expr);
+    let output_format = fields
+        .remove("output_format")
+        .unwrap_or_else(|| parse_quote!(category == crate::command::Category::Image));
 
     let following = quote::quote! {
         #[allow(non_camel_case_types)]
@@ -218,6 +221,14 @@ pub fn command(attrs: TokenStream, func: TokenStream) -> TokenStream {
                 for (k, v) in #flag_descriptions {
                     descriptions.insert(k, v);
                 }
+                let category = #category;
+                let output_format = #output_format;
+                if output_format {
+                    descriptions.insert(
+                        crate::command::flags::OUTPUT_FORMAT_FLAG,
+                        crate::command::flags::OUTPUT_FORMAT_DESCRIPTION
+                    );
+                }
 
                 static META: std::sync::OnceLock<crate::command::CommandMetadata> = std::sync::OnceLock::new();
                 META.get_or_init(|| crate::command::CommandMetadata {
@@ -226,7 +237,7 @@ pub fn command(attrs: TokenStream, func: TokenStream) -> TokenStream {
                     access: #access,
                     name: #name,
                     aliases: &#aliases,
-                    category: #category,
+                    category,
                     examples: &#examples,
                     usage: format!("{}", #usage),
                     send_processing: #send_processing,
@@ -235,7 +246,8 @@ pub fn command(attrs: TokenStream, func: TokenStream) -> TokenStream {
                     context_menu_message_command: #context_menu_message_command,
                     context_menu_user_command: #context_menu_user_command,
                     guild_only: #guild_only,
-                    group_parent_name: #group_parent_name
+                    group_parent_name: #group_parent_name,
+                    output_format
                 })
             }
 
@@ -298,6 +310,9 @@ pub fn command(attrs: TokenStream, func: TokenStream) -> TokenStream {
                 #(
                   command_options.extend(#command_option_exprs);
                 )*
+                if self.metadata().output_format {
+                    command_options.push(crate::command::flags::output_format_option());
+                }
 
                 let command_info = crate::command::CommandInteractionInfo { command_options };
                 crate::command::CommandGroupingInteractionInfo::Command(command_info)
@@ -312,11 +327,21 @@ pub fn command(attrs: TokenStream, func: TokenStream) -> TokenStream {
 
                 crate::command::check_metadata(self.metadata(), &mut ctxt.cx).await?;
 
+                // the format flag is taken out before parsing, so that no argument sees it
+                let raw_args = ctxt.rest_all(None);
+                let (args, output_format) = crate::command::flags::take_output_format(self.metadata(), &raw_args)
+                    .map_err(crate::command::ExecutionError::Parse)?;
+                #[allow(unused_mut)]
+                let mut ctxt = crate::command::RawMessageParseCtxt::new(ctxt.cx, &args);
+
                 #(
                     let #parse_idents = #parse_exprs.map_err(crate::command::ExecutionError::Parse)?;
                 )*
 
-                #fn_name(ctxt.cx, #(#parse_idents),*).await.map_err(crate::command::ExecutionError::Command)
+                assyst_flux_iface::flux_request::OUTPUT_FORMAT
+                    .scope(output_format, #fn_name(ctxt.cx, #(#parse_idents),*))
+                    .await
+                    .map_err(crate::command::ExecutionError::Command)
             }
 
             async fn execute_interaction_command(
@@ -328,11 +353,17 @@ pub fn command(attrs: TokenStream, func: TokenStream) -> TokenStream {
 
                 crate::command::check_metadata(self.metadata(), &mut ctxt.cx).await?;
 
+                let output_format = crate::command::flags::output_format_from_option(self.metadata(), &mut ctxt)
+                    .map_err(crate::command::ExecutionError::Parse)?;
+
                 #(
                     let #parse_idents = #interaction_parse_exprs.map_err(crate::command::ExecutionError::Parse)?;
                 )*
 
-                #fn_name(ctxt.cx, #(#parse_idents),*).await.map_err(crate::command::ExecutionError::Command)
+                assyst_flux_iface::flux_request::OUTPUT_FORMAT
+                    .scope(output_format, #fn_name(ctxt.cx, #(#parse_idents),*))
+                    .await
+                    .map_err(crate::command::ExecutionError::Command)
             }
 
             #[allow(unreachable_code)]
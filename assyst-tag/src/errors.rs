@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 use std::fmt::Arguments;
 use std::ops::Range;
+use std::time::Duration;
 
 use assyst_string_fmt::Ansi;
 use memchr::memmem::rfind;
@@ -25,6 +26,16 @@ pub enum ErrorKind {
         /// Position at which the limit was exceeded
         pos: BytePos,
     },
+    /// Execution time limit exceeded
+    TimeLimit {
+        /// Position at which the deadline had passed
+        pos: BytePos,
+    },
+    /// The user or the tag has used up its execution budget, and the tag was not ran
+    ExecutionBudget {
+        scope: BudgetScope,
+        retry_after: Duration,
+    },
     EmptySubtag {
         span: Range<usize>,
     },
@@ -53,6 +64,14 @@ pub enum ErrorKind {
     RequestLimit {
         span: Range<usize>,
     },
+    ComponentLimit {
+        span: Range<usize>,
+        limit: usize,
+    },
+    ComponentLabelLengthLimit {
+        length: usize,
+        span: Range<usize>,
+    },
     ArgParseError {
         span: Range<usize>,
         err: subtags::ParseError,
@@ -105,6 +124,29 @@ pub enum ErrorKind {
     },
 }
 
+impl ErrorKind {
+    /// Whether this error must stop parsing even in `ParseMode::CollectDiagnostics`, because it
+    /// means that a limit was hit
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            ErrorKind::IterLimit { .. }
+            | ErrorKind::TimeLimit { .. }
+            | ErrorKind::ExecutionBudget { .. }
+            | ErrorKind::DepthLimit { .. }
+            | ErrorKind::StringLengthLimit { .. } => true,
+            ErrorKind::Nested { error, .. } => error.kind.is_fatal(),
+            _ => false,
+        }
+    }
+}
+
+/// What an execution budget is tracked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetScope {
+    User,
+    Tag,
+}
+
 #[derive(Debug, Clone)]
 pub struct Error {
     // inner error is boxed because we want to keep the size of `Result<_, Error>` small with
@@ -114,6 +156,25 @@ pub struct Error {
 
 pub type TResult<T> = Result<T, Error>;
 
+/// An error that was recovered from in `ParseMode::CollectDiagnostics`
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// Byte range of the subtag in the input that the error was recovered at
+    pub span: Range<usize>,
+    pub kind: ErrorKind,
+    /// Short, uncolored description of the error
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(src: &str, span: Range<usize>, err: Error) -> Self {
+        let kind = (*err.kind).clone();
+        let message = error_message(src, err);
+
+        Self { span, kind, message }
+    }
+}
+
 pub fn wrap_anyhow(at: Range<usize>, res: anyhow::Error) -> Error {
     err(ErrorKind::Unknown {
         span: at,
@@ -257,6 +318,26 @@ impl<'buf> DiagnosticBuilder<'buf> {
 }
 
 pub fn format_error(src: &str, err: Error) -> String {
+    if let ErrorKind::Nested { source, error } = *err.kind {
+        return format_error(&source, error);
+    }
+
+    diagnostic_builder(src, err).into_string()
+}
+
+/// Returns just the message of an error, without any of its notes
+pub fn error_message(src: &str, err: Error) -> String {
+    if let ErrorKind::Nested { source, error } = *err.kind {
+        return error_message(&source, error);
+    }
+
+    diagnostic_builder(src, err)
+        .message
+        .expect("no message set for diagnostic")
+        .into_owned()
+}
+
+fn diagnostic_builder(src: &str, err: Error) -> DiagnosticBuilder<'_> {
     fn char_index_to_span(src: &str, index: usize) -> Range<usize> {
         let lo = src.floor_char_boundary(index);
         if let Some(c) = src[lo..].chars().next() {
@@ -267,10 +348,6 @@ pub fn format_error(src: &str, err: Error) -> String {
         }
     }
 
-    if let ErrorKind::Nested { source, error } = *err.kind {
-        return format_error(&source, error);
-    }
-
     let mut db = DiagnosticBuilder {
         src,
         kind: DiagnosticKind::Error,
@@ -397,6 +474,25 @@ pub fn format_error(src: &str, err: Error) -> String {
                 span: Some(char_index_to_span(src, pos)),
             });
         },
+        ErrorKind::TimeLimit { pos } => {
+            db.message = Some("tag execution time limit exceeded".into());
+            db.span_notes.push(Note {
+                kind: NoteKind::Error,
+                message: "ran out of execution time while processing this token".into(),
+                span: Some(char_index_to_span(src, pos)),
+            });
+        },
+        ErrorKind::ExecutionBudget { scope, retry_after } => {
+            db.message = Some(match scope {
+                BudgetScope::User => "you have used up your tag execution time for now".into(),
+                BudgetScope::Tag => "this tag has used up its execution time for now".into(),
+            });
+            db.span_notes.push(Note {
+                kind: NoteKind::Help,
+                message: format!("try again in {} seconds", retry_after.as_secs().max(1)).into(),
+                span: None,
+            });
+        },
         ErrorKind::MissingClosingBrace {
             expected_position,
             tag_start,
@@ -456,6 +552,20 @@ pub fn format_error(src: &str, err: Error) -> String {
             format_args!("maximum number of http requests ({}) reached", limits::MAX_REQUESTS),
             Some(span),
         ),
+        ErrorKind::ComponentLimit { span, limit } => simple_span_diag(
+            &mut db,
+            format_args!("cannot declare more than {limit} components of this kind"),
+            Some(span),
+        ),
+        ErrorKind::ComponentLabelLengthLimit { span, length } => simple_span_diag(
+            &mut db,
+            format_args!(
+                "component label is too long ({}>{})",
+                length,
+                limits::MAX_COMPONENT_LABEL_LENGTH,
+            ),
+            Some(span),
+        ),
         ErrorKind::IfMissingStmt { span } => simple_span_diag(
             &mut db,
             format_args!("`if` tag is missing a value to compare"),
@@ -491,5 +601,5 @@ pub fn format_error(src: &str, err: Error) -> String {
         },
     }
 
-    db.into_string()
+    db
 }
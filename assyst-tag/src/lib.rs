@@ -3,11 +3,12 @@
 
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::time::Instant;
 
 use assyst_common::util::filetype::Type;
 pub use context::{Context, NopContext};
-use errors::TResult;
-use parser::{Counter, ParseMode, Parser, SharedState};
+use errors::{Diagnostic, TResult};
+use parser::{Counter, ParseMode, Parser, SharedState, TagComponent, limits};
 
 mod context;
 pub mod errors;
@@ -18,19 +19,38 @@ mod subtags;
 pub struct ParseResult {
     pub output: String,
     pub attachment: Option<(Vec<u8>, Type)>,
+    pub components: Vec<TagComponent>,
+    /// Errors that were recovered from, only collected in `ParseMode::CollectDiagnostics`
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 pub fn parse<C: Context>(input: &str, args: &[&str], mode: ParseMode, cx: C) -> TResult<ParseResult> {
+    parse_with_deadline(input, args, mode, cx, Instant::now() + limits::MAX_EXECUTION_TIME)
+}
+
+/// Like [`parse`], but aborts with `ErrorKind::TimeLimit` once `deadline` has passed
+pub fn parse_with_deadline<C: Context>(
+    input: &str,
+    args: &[&str],
+    mode: ParseMode,
+    cx: C,
+    deadline: Instant,
+) -> TResult<ParseResult> {
     let variables = RefCell::new(HashMap::new());
-    let counter = Counter::default();
+    let counter = Counter::with_deadline(deadline);
     let attachment = RefCell::new(None);
-    let state = SharedState::new(&variables, &counter, &attachment);
+    let components = RefCell::new(Vec::new());
+    let state = SharedState::new(&variables, &counter, &attachment, &components);
 
-    let output = Parser::new(input.as_bytes(), args, state, mode, &cx).parse_segment(true)?;
+    let mut parser = Parser::new(input.as_bytes(), args, state, mode, &cx);
+    let output = parser.parse_segment(true)?;
+    let diagnostics = parser.take_diagnostics();
 
     Ok(ParseResult {
         output,
         attachment: attachment.into_inner(),
+        components: components.into_inner(),
+        diagnostics,
     })
 }
 
@@ -80,6 +100,8 @@ mod tests {
         spoiler2: "a||b||" => Ok("a||b||"),
         spoiler_in_subparser: "{eval:a||b||c}" => Ok("a"),
         spoiler_in_subtag: "{note:a||b||c}" => Err(ErrorKind::MissingClosingBrace { .. }),
+        button: "{button:yes|1}{select:no}ok" => Ok("ok"),
+        button_limit: &"{button:a}".repeat(6) => Err(ErrorKind::ComponentLimit { .. }),
     );
 
     test!(ParseMode::IgnoreOnError;
@@ -89,4 +111,36 @@ mod tests {
         asynk: "(async () => {...})" => Ok("(async () => {...})"),
         asynk2: "(async () => { return 42 })" => Ok("(async () => { return 42 })")
     );
+
+    test!(ParseMode::CollectDiagnostics;
+        collect_unknown: "a{foo:42}b" => Ok("a{foo:42}b"),
+        collect_arg_parse: "{max:x}{max:1|2}" => Ok("{max:x}2"),
+        collect_missing_brace: "a{args" => Ok("a{args"),
+        collect_iter_limit: &"{max:0}".repeat(501) => Err(ErrorKind::IterLimit{..}),
+    );
+
+    #[test]
+    fn collect_diagnostics() {
+        let input = "a{foo:42}b{max:x}";
+        let res = parse(input, &[], ParseMode::CollectDiagnostics, NopContext).unwrap();
+
+        assert_eq!(res.diagnostics.len(), 2);
+        assert!(matches!(res.diagnostics[0].kind, ErrorKind::UnknownSubtag { .. }));
+        assert_eq!(&input[res.diagnostics[0].span.clone()], "{foo:42}");
+        assert!(matches!(res.diagnostics[1].kind, ErrorKind::ArgParseError { .. }));
+        assert_eq!(&input[res.diagnostics[1].span.clone()], "{max:x}");
+    }
+
+    #[test]
+    fn time_limit() {
+        let input = "{max:0}";
+        let res = parse_with_deadline(input, &[], ParseMode::IgnoreOnError, NopContext, Instant::now());
+        assert!(matches!(
+            res.as_ref().map_err(|err| &*err.kind),
+            Err(ErrorKind::TimeLimit { .. })
+        ));
+        if let Err(err) = res {
+            errors::format_error(input, err);
+        }
+    }
 }
@@ -2,17 +2,19 @@ use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::error::Error as StdError;
 use std::ops::Range;
+use std::time::Instant;
 
 use assyst_common::util::filetype::Type;
 use rand::prelude::ThreadRng;
 
 use crate::context::Context;
-use crate::errors::{BytePos, ErrorKind, TResult, err_res};
+use crate::errors::{BytePos, Diagnostic, Error, ErrorKind, TResult, err, err_res};
 use crate::subtags;
 
 /// Constants and helper functions for tag parser limits
 pub mod limits {
     use std::cell::Cell;
+    use std::time::Duration;
 
     pub const MAX_REQUESTS: u32 = 5;
     pub const MAX_VARIABLES: usize = 100;
@@ -21,6 +23,11 @@ pub mod limits {
     pub const MAX_ITERATIONS: u32 = 500;
     pub const MAX_DEPTH: u32 = 15;
     pub const MAX_STRING_LENGTH: usize = 256_000;
+    pub const MAX_BUTTONS: usize = 5;
+    pub const MAX_SELECT_OPTIONS: usize = 25;
+    pub const MAX_COMPONENT_LABEL_LENGTH: usize = 80;
+    /// Wall-clock time a single tag is allowed to run for
+    pub const MAX_EXECUTION_TIME: Duration = Duration::from_secs(10);
 
     pub fn try_increment(field_cell: &Cell<u32>, limit: u32) -> bool {
         let field = field_cell.get();
@@ -44,6 +51,8 @@ pub struct SharedState<'a> {
     counter: &'a Counter,
     /// The attachment to be responded with, if set
     attachment: &'a RefCell<Option<(Vec<u8>, Type)>>,
+    /// Interactive components to be attached to the response
+    components: &'a RefCell<Vec<TagComponent>>,
 }
 
 impl<'a> SharedState<'a> {
@@ -51,11 +60,13 @@ impl<'a> SharedState<'a> {
         variables: &'a RefCell<HashMap<String, String>>,
         counter: &'a Counter,
         attachment: &'a RefCell<Option<(Vec<u8>, Type)>>,
+        components: &'a RefCell<Vec<TagComponent>>,
     ) -> Self {
         Self {
             variables,
             counter,
             attachment,
+            components,
         }
     }
 
@@ -86,6 +97,40 @@ impl<'a> SharedState<'a> {
     pub fn set_attachment(&self, buf: Vec<u8>, ty: Type) {
         *self.attachment.borrow_mut() = Some((buf, ty));
     }
+
+    /// Calls `f` with a mutable reference to the components declared so far
+    pub fn with_components_mut<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut Vec<TagComponent>) -> T,
+    {
+        let mut components = self.components.borrow_mut();
+        f(&mut components)
+    }
+}
+
+/// An interactive component declared by a tag
+///
+/// When a user interacts with it, the tag is executed again with `payload` as its arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagComponent {
+    /// A button, declared with `{button:label|payload}`
+    Button { label: String, payload: String },
+    /// An option of the select menu, declared with `{select:label|payload}`
+    SelectOption { label: String, payload: String },
+}
+
+impl TagComponent {
+    pub fn label(&self) -> &str {
+        match self {
+            TagComponent::Button { label, .. } | TagComponent::SelectOption { label, .. } => label,
+        }
+    }
+
+    pub fn payload(&self) -> &str {
+        match self {
+            TagComponent::Button { payload, .. } | TagComponent::SelectOption { payload, .. } => payload,
+        }
+    }
 }
 
 /// Counter for various limits
@@ -95,9 +140,18 @@ pub struct Counter {
     requests: Cell<u32>,
     /// Number of parser iterations
     iterations: Cell<u32>,
+    /// Point in time after which parsing is aborted
+    deadline: Option<Instant>,
 }
 
 impl Counter {
+    pub fn with_deadline(deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..Default::default()
+        }
+    }
+
     /// Tries to increment the requests field if it's not already at the limit
     pub fn try_request(&self) -> bool {
         limits::try_increment(&self.requests, limits::MAX_REQUESTS)
@@ -107,6 +161,11 @@ impl Counter {
     pub fn try_iterate(&self) -> bool {
         limits::try_increment(&self.iterations, limits::MAX_ITERATIONS)
     }
+
+    /// Checks whether the deadline, if any, has passed
+    pub fn past_deadline(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
 }
 
 #[derive(Default, Copy, Clone, Debug)]
@@ -119,6 +178,13 @@ pub enum ParseMode {
     /// Stop as soon as an error occurs and report it to the user
     #[default]
     StopOnError,
+    /// Like `IgnoreOnError`, but recovers from every error that is not fatal (such as hitting a
+    /// limit) and records each one as a [`Diagnostic`], so that the output can be shown alongside
+    /// them.
+    ///
+    /// Subparsers stop on error instead, so that their errors surface at the subtag in the
+    /// original input that created them.
+    CollectDiagnostics,
 }
 
 /// The tag parser
@@ -142,6 +208,8 @@ pub struct Parser<'a> {
     /// Stack of tag start positions.
     /// Note that this also includes the root text node.
     tag_start_positions: Vec<BytePos>,
+    /// Errors recovered from in `ParseMode::CollectDiagnostics`
+    diagnostics: Vec<Diagnostic>,
 }
 
 /// Checks if a given byte is in the a..z A..Z range
@@ -164,7 +232,10 @@ impl<'a> Parser<'a> {
     pub fn from_parent_with_args(input: &'a [u8], other: &Self, args: &'a [&'a str]) -> Self {
         Self {
             input,
-            mode: other.mode,
+            mode: match other.mode {
+                ParseMode::CollectDiagnostics => ParseMode::StopOnError,
+                mode => mode,
+            },
             args,
             idx: 0,
             state: other.state.clone(),
@@ -172,6 +243,7 @@ impl<'a> Parser<'a> {
             cx: other.cx,
             subparser_depth: other.subparser_depth + 1,
             tag_start_positions: Vec::new(),
+            diagnostics: Vec::new(),
         }
     }
 
@@ -193,6 +265,7 @@ impl<'a> Parser<'a> {
             rng: rand::thread_rng(),
             subparser_depth: 0,
             tag_start_positions: Vec::new(),
+            diagnostics: Vec::new(),
         }
     }
 
@@ -285,6 +358,10 @@ impl<'a> Parser<'a> {
 
             match byte {
                 b'{' => {
+                    if self.state.counter.past_deadline() {
+                        return err_res(ErrorKind::TimeLimit { pos: self.idx });
+                    }
+
                     *self.tag_start_positions.last_mut().unwrap() = self.idx;
                     // skip {
                     self.idx += 1;
@@ -303,7 +380,10 @@ impl<'a> Parser<'a> {
                     };
 
                     if is_meta_tag && name == "ignore_parse_errors" {
-                        self.mode = ParseMode::IgnoreOnError;
+                        // collecting diagnostics already recovers from these errors
+                        if !matches!(self.mode, ParseMode::CollectDiagnostics) {
+                            self.mode = ParseMode::IgnoreOnError;
+                        }
                         self.expect_closing_brace()?;
                         continue;
                     }
@@ -315,6 +395,14 @@ impl<'a> Parser<'a> {
                                 output.extend(&self.input[self.span()]);
                                 continue;
                             },
+                            ParseMode::CollectDiagnostics => {
+                                self.record_diagnostic(err(ErrorKind::MissingClosingBrace {
+                                    expected_position: self.idx,
+                                    tag_start: self.last_tag_start_pos(),
+                                }));
+                                output.extend(&self.input[self.span()]);
+                                continue;
+                            },
                             ParseMode::StopOnError => {
                                 return err_res(ErrorKind::MissingClosingBrace {
                                     expected_position: self.idx,
@@ -330,6 +418,11 @@ impl<'a> Parser<'a> {
                                 self.recover_parse_error(&mut output)?;
                                 continue;
                             },
+                            ParseMode::CollectDiagnostics => {
+                                self.record_diagnostic(err(ErrorKind::EmptySubtag { span: self.span() }));
+                                self.recover_parse_error(&mut output)?;
+                                continue;
+                            },
                             ParseMode::StopOnError => {
                                 return err_res(ErrorKind::EmptySubtag { span: self.span() });
                             },
@@ -361,6 +454,14 @@ impl<'a> Parser<'a> {
                                 self.recover_parse_error(&mut output)?;
                                 continue;
                             },
+                            ParseMode::CollectDiagnostics => {
+                                self.record_diagnostic(err(ErrorKind::MissingClosingBrace {
+                                    expected_position: closing_brace,
+                                    tag_start: self.last_tag_start_pos(),
+                                }));
+                                self.recover_parse_error(&mut output)?;
+                                continue;
+                            },
                             ParseMode::StopOnError => {
                                 return err_res(ErrorKind::MissingClosingBrace {
                                     expected_position: closing_brace,
@@ -380,6 +481,13 @@ impl<'a> Parser<'a> {
                                     .unwrap_or_else(|err| self.unreachable_invalid_utf8(err))
                                     .to_owned()
                             },
+                            (Err(err), ParseMode::CollectDiagnostics) if !err.kind.is_fatal() => {
+                                self.record_diagnostic(err);
+
+                                std::str::from_utf8(&self.input[self.span()])
+                                    .unwrap_or_else(|err| self.unreachable_invalid_utf8(err))
+                                    .to_owned()
+                            },
                             (Err(err), _) => return Err(err),
                         }
                     } else {
@@ -440,6 +548,19 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
+    /// Records an error that was recovered from, pointing at the current tag.
+    /// Only call this within a tag context, in `ParseMode::CollectDiagnostics`.
+    fn record_diagnostic(&mut self, err: Error) {
+        let src = std::str::from_utf8(self.input).unwrap_or_else(|err| self.unreachable_invalid_utf8(err));
+        let diagnostic = Diagnostic::new(src, self.span(), err);
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Takes the diagnostics recorded so far
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
     fn expect_closing_brace(&mut self) -> TResult<()> {
         if !self.eat(b"}") {
             return err_res(ErrorKind::MissingClosingBrace {
@@ -463,6 +584,9 @@ impl<'a> Parser<'a> {
         if !self.state.counter.try_iterate() {
             return err_res(ErrorKind::IterLimit { pos: self.idx });
         }
+        if self.state.counter.past_deadline() {
+            return err_res(ErrorKind::TimeLimit { pos: self.idx });
+        }
         self.tag_start_positions.push(self.idx);
         #[allow(deprecated)]
         let res = self.parse_segment_inner_untracked(side_effects);
@@ -522,6 +646,8 @@ impl<'a> Parser<'a> {
             "idof" => subtags::exec(self, &args, subtags::idof),
             "userid" => subtags::exec(self, &args, subtags::userid),
             "tag" => subtags::exec(self, &args, subtags::tag),
+            "button" => subtags::exec(self, &args, subtags::button),
+            "select" => subtags::exec(self, &args, subtags::select),
             _ => err_res(ErrorKind::UnknownSubtag {
                 name: name.to_owned(),
                 span: name_span,
@@ -7,9 +7,10 @@ use rand::Rng;
 
 use crate::errors::{err, err_res, wrap_anyhow, ErrorKind, TResult};
 use crate::parser::limits::{
-    MAX_DEPTH, MAX_STRING_LENGTH, MAX_VARIABLES, MAX_VARIABLE_KEY_LENGTH, MAX_VARIABLE_VALUE_LENGTH,
+    MAX_BUTTONS, MAX_COMPONENT_LABEL_LENGTH, MAX_DEPTH, MAX_SELECT_OPTIONS, MAX_STRING_LENGTH, MAX_VARIABLES,
+    MAX_VARIABLE_KEY_LENGTH, MAX_VARIABLE_VALUE_LENGTH,
 };
-use crate::parser::Parser;
+use crate::parser::{Parser, TagComponent};
 
 /// Ensures that the HTTP request limit has not been hit yet
 ///
@@ -612,3 +613,54 @@ pub fn tag(parser: &mut Parser<'_>, (name, Rest(args)): (String, Rest<String>))
 
     Parser::from_parent_with_args(content.as_bytes(), parser, &args).parse_segment(true)
 }
+
+/// Declares a component, making sure that the limit for its kind is not exceeded
+fn add_component(parser: &mut Parser<'_>, component: TagComponent) -> TResult<String> {
+    let label_length = component.label().chars().count();
+    if label_length == 0 || label_length > MAX_COMPONENT_LABEL_LENGTH {
+        return err_res(ErrorKind::ComponentLabelLengthLimit {
+            span: parser.span(),
+            length: label_length,
+        });
+    }
+
+    parser.state().with_components_mut(|components| -> TResult<String> {
+        let (count, limit) = match component {
+            TagComponent::Button { .. } => (
+                components
+                    .iter()
+                    .filter(|c| matches!(c, TagComponent::Button { .. }))
+                    .count(),
+                MAX_BUTTONS,
+            ),
+            TagComponent::SelectOption { .. } => (
+                components
+                    .iter()
+                    .filter(|c| matches!(c, TagComponent::SelectOption { .. }))
+                    .count(),
+                MAX_SELECT_OPTIONS,
+            ),
+        };
+
+        if count >= limit {
+            return err_res(ErrorKind::ComponentLimit {
+                span: parser.span(),
+                limit,
+            });
+        }
+
+        components.push(component);
+
+        Ok(String::new())
+    })
+}
+
+pub fn button(parser: &mut Parser<'_>, (label, payload): (String, Option<String>)) -> TResult<String> {
+    let payload = payload.unwrap_or_else(|| label.clone());
+    add_component(parser, TagComponent::Button { label, payload })
+}
+
+pub fn select(parser: &mut Parser<'_>, (label, payload): (String, Option<String>)) -> TResult<String> {
+    let payload = payload.unwrap_or_else(|| label.clone());
+    add_component(parser, TagComponent::SelectOption { label, payload })
+}